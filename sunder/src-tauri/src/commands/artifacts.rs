@@ -0,0 +1,25 @@
+use super::scan::ScanState;
+use crate::engine::artifacts::{scan_artifacts, ArtifactOptions, ArtifactScan};
+use crate::engine::scan::validate_root;
+
+/// Deep scan for build artifacts below `root`, or below home when no root
+/// is given. Shares the scan cancel token with the other scans.
+#[tauri::command]
+pub async fn scan_dev_artifacts(
+    window: tauri::Window,
+    state: tauri::State<'_, ScanState>,
+    root: Option<String>,
+    options: Option<ArtifactOptions>,
+) -> Result<ArtifactScan, String> {
+    let root_path = match root {
+        Some(root) => validate_root(&root)?,
+        None => dirs::home_dir().ok_or("Could not resolve home directory")?,
+    };
+    let options = options.unwrap_or_default();
+    let cancel = state.start(&root_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_artifacts(&root_path, &options, &window, &cancel)
+    })
+    .await
+    .map_err(|err| format!("Artifact scan worker failed: {err}"))
+}
@@ -0,0 +1,23 @@
+use tauri::Manager;
+
+use crate::engine::audit::{AuditLog, AuditPage, AuditQuery, AUDIT_LOG_FILE};
+
+/// The purge audit log under the app data dir.
+pub fn audit_log(app: &tauri::AppHandle) -> Result<AuditLog, String> {
+    app.path()
+        .app_data_dir()
+        .map(|data_dir| AuditLog::new(data_dir.join(AUDIT_LOG_FILE)))
+        .map_err(|err| format!("Could not resolve data directory: {err}"))
+}
+
+#[tauri::command]
+pub async fn get_audit_log(
+    app: tauri::AppHandle,
+    query: Option<AuditQuery>,
+) -> Result<AuditPage, String> {
+    let log = audit_log(&app)?;
+    let query = query.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || log.read(&query))
+        .await
+        .map_err(|err| format!("Audit reader failed: {err}"))?
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use super::audit::audit_log;
+use super::purge::path_guard;
+use super::scan::ScanState;
+use crate::engine::cancel::CancelToken;
+use crate::engine::cleanup::{
+    clean_cache as run_cleanup, preview_cleanup, CleanupPreview, CleanupReport, SystemTools,
+};
+use crate::engine::purge::SystemTrash;
+
+#[tauri::command]
+pub async fn preview_cache_cleanup(path: String) -> Result<CleanupPreview, String> {
+    let cancel = CancelToken::new();
+    tauri::async_runtime::spawn_blocking(move || {
+        preview_cleanup(&PathBuf::from(path), &SystemTools, &cancel)
+    })
+    .await
+    .map_err(|err| format!("Cleanup preview failed: {err}"))?
+}
+
+#[tauri::command]
+pub async fn clean_cache(
+    app: tauri::AppHandle,
+    scan_state: tauri::State<'_, ScanState>,
+    root: String,
+    path: String,
+) -> Result<CleanupReport, String> {
+    let guard = path_guard(&app, &scan_state, &root)?;
+    let audit = audit_log(&app)?;
+    let cancel = CancelToken::new();
+    tauri::async_runtime::spawn_blocking(move || {
+        run_cleanup(
+            &PathBuf::from(path),
+            &guard,
+            &SystemTools,
+            &SystemTrash,
+            &audit,
+            &cancel,
+        )
+    })
+    .await
+    .map_err(|err| format!("Cleanup worker failed: {err}"))?
+}
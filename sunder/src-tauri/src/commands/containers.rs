@@ -0,0 +1,27 @@
+use super::audit::audit_log;
+use crate::engine::containers::{EngineClient, EngineDiskUsage, PruneReport, PruneSelection};
+
+fn engine_client() -> Result<EngineClient, String> {
+    EngineClient::detect().ok_or_else(|| "No Docker or Podman socket found".into())
+}
+
+#[tauri::command]
+pub async fn container_disk_usage() -> Result<EngineDiskUsage, String> {
+    let client = engine_client()?;
+    tauri::async_runtime::spawn_blocking(move || client.disk_usage())
+        .await
+        .map_err(|err| format!("Container engine query failed: {err}"))?
+}
+
+/// Removes the reclaimable items the user reviewed, by id.
+#[tauri::command]
+pub async fn prune_container_resources(
+    app: tauri::AppHandle,
+    selection: PruneSelection,
+) -> Result<PruneReport, String> {
+    let client = engine_client()?;
+    let audit = audit_log(&app)?;
+    tauri::async_runtime::spawn_blocking(move || client.prune(&selection, &audit))
+        .await
+        .map_err(|err| format!("Container prune failed: {err}"))?
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::audit::audit_log;
+use super::purge::path_guard;
+use super::rules::rule_set;
+use super::scan::ScanState;
+use crate::engine::cancel::CancelToken;
+use crate::engine::dedupe::{execute_dedupe, plan_dedupe, DedupePlan, DedupeReport, SystemLinker};
+use crate::engine::duplicates::DuplicateGroup;
+use crate::engine::mounts::MountTable;
+
+/// Dedupe plans waiting for the user to confirm them, with the root each
+/// was planned against. Like purge plans, each one runs at most once.
+#[derive(Default)]
+pub struct DedupeState {
+    plans: Mutex<HashMap<String, (String, DedupePlan)>>,
+}
+
+/// Dry run of replacing every copy in `group` but `keep` with a link.
+#[tauri::command]
+pub async fn plan_dedupe_group(
+    app: tauri::AppHandle,
+    scan_state: tauri::State<'_, ScanState>,
+    dedupe_state: tauri::State<'_, DedupeState>,
+    root: String,
+    group: DuplicateGroup,
+    keep: Option<String>,
+) -> Result<DedupePlan, String> {
+    let guard = path_guard(&app, &scan_state, &root)?;
+    let rules = rule_set(&app)?;
+    let plan = tauri::async_runtime::spawn_blocking(move || {
+        plan_dedupe(&group, keep.as_deref(), &guard, &rules, &MountTable::load())
+    })
+    .await
+    .map_err(|err| format!("Dedupe planner failed: {err}"))??;
+
+    dedupe_state
+        .plans
+        .lock()
+        .map_err(|_| "Dedupe state is unavailable")?
+        .insert(plan.plan_id.clone(), (root, plan.clone()));
+    Ok(plan)
+}
+
+#[tauri::command]
+pub async fn dedupe_group(
+    app: tauri::AppHandle,
+    scan_state: tauri::State<'_, ScanState>,
+    dedupe_state: tauri::State<'_, DedupeState>,
+    plan_id: String,
+) -> Result<DedupeReport, String> {
+    let audit = audit_log(&app)?;
+    let (root, plan) = dedupe_state
+        .plans
+        .lock()
+        .map_err(|_| "Dedupe state is unavailable")?
+        .remove(&plan_id)
+        .ok_or_else(|| format!("Unknown or already executed plan {plan_id}"))?;
+    let guard = path_guard(&app, &scan_state, &root)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        execute_dedupe(&plan, &guard, &SystemLinker, &audit, &CancelToken::new())
+    })
+    .await
+    .map_err(|err| format!("Dedupe worker failed: {err}"))?
+}
@@ -0,0 +1,40 @@
+use super::audit::audit_log;
+use super::purge::home_guard;
+use super::scan::ScanState;
+use crate::engine::cancel::CancelToken;
+use crate::engine::cleanup::{CleanupReport, SystemTools};
+use crate::engine::devpurge::{clean_dev_cache, scan_dev_caches, DevPurgeScan};
+use crate::engine::purge::SystemTrash;
+use crate::engine::xdg::XdgDirs;
+
+#[tauri::command]
+pub async fn dev_purge_scan(state: tauri::State<'_, ScanState>) -> Result<DevPurgeScan, String> {
+    let home = dirs::home_dir().ok_or("Could not resolve home directory")?;
+    let xdg = XdgDirs::from_env(&home);
+    let cancel = state.cancel_token();
+    tauri::async_runtime::spawn_blocking(move || scan_dev_caches(&xdg, &SystemTools, &cancel))
+        .await
+        .map_err(|err| format!("Dev-Purge scan failed: {err}"))
+}
+
+#[tauri::command]
+pub async fn dev_purge_clean(app: tauri::AppHandle, id: String) -> Result<CleanupReport, String> {
+    let home = dirs::home_dir().ok_or("Could not resolve home directory")?;
+    let xdg = XdgDirs::from_env(&home);
+    let guard = home_guard(&app)?;
+    let audit = audit_log(&app)?;
+    let cancel = CancelToken::new();
+    tauri::async_runtime::spawn_blocking(move || {
+        clean_dev_cache(
+            &xdg,
+            &id,
+            &guard,
+            &SystemTools,
+            &SystemTrash,
+            &audit,
+            &cancel,
+        )
+    })
+    .await
+    .map_err(|err| format!("Dev-Purge worker failed: {err}"))?
+}
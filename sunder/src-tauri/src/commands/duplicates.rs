@@ -0,0 +1,25 @@
+use super::scan::ScanState;
+use crate::engine::duplicates::{self, DuplicateScan, DEFAULT_MIN_SIZE};
+use crate::engine::scan::validate_root;
+
+/// Duplicate files of at least `min_size` bytes below `root`, or below home
+/// when no root is given. Shares the scan cancel token with the other scans.
+#[tauri::command]
+pub async fn find_duplicates(
+    window: tauri::Window,
+    state: tauri::State<'_, ScanState>,
+    root: Option<String>,
+    min_size: Option<u64>,
+) -> Result<DuplicateScan, String> {
+    let root_path = match root {
+        Some(root) => validate_root(&root)?,
+        None => dirs::home_dir().ok_or("Could not resolve home directory")?,
+    };
+    let min_size = min_size.unwrap_or(DEFAULT_MIN_SIZE);
+    let cancel = state.start(&root_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        duplicates::find_duplicates(&root_path, min_size, &window, &cancel)
+    })
+    .await
+    .map_err(|err| format!("Duplicate scan worker failed: {err}"))
+}
@@ -0,0 +1,25 @@
+use super::scan::ScanState;
+use crate::engine::largest::{find_largest_files, LargestFilesOptions, LargestFilesScan};
+use crate::engine::scan::validate_root;
+
+/// The largest files below `root`, or below home when no root is given.
+/// Shares the scan cancel token with the other scans.
+#[tauri::command]
+pub async fn largest_files(
+    window: tauri::Window,
+    state: tauri::State<'_, ScanState>,
+    root: Option<String>,
+    options: Option<LargestFilesOptions>,
+) -> Result<LargestFilesScan, String> {
+    let root_path = match root {
+        Some(root) => validate_root(&root)?,
+        None => dirs::home_dir().ok_or("Could not resolve home directory")?,
+    };
+    let options = options.unwrap_or_default();
+    let cancel = state.start(&root_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        find_largest_files(&root_path, &options, &window, &cancel)
+    })
+    .await
+    .map_err(|err| format!("Largest files worker failed: {err}"))?
+}
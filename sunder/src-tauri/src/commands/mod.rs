@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+use tauri::{Emitter, Manager};
+
+use crate::engine::progress::{ProgressSink, ScanProgress};
+
+pub mod artifacts;
+pub mod audit;
+pub mod cleanup;
+pub mod containers;
+pub mod dedupe;
+pub mod devpurge;
+pub mod duplicates;
+pub mod largest;
+pub mod purge;
+pub mod restore;
+pub mod rules;
+pub mod scan;
+pub mod snapshots;
+pub mod tree;
+
+impl ProgressSink for tauri::Window {
+    fn report(&self, progress: ScanProgress) {
+        let _ = self.emit("scan-progress", progress);
+    }
+}
+
+/// A settings file in the app config dir.
+fn config_file(app: &tauri::AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|config_dir| config_dir.join(file_name))
+        .map_err(|err| format!("Could not resolve config directory: {err}"))
+}
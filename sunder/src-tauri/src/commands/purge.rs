@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::audit::audit_log;
+use super::config_file;
+use super::rules::rule_set;
+use super::scan::ScanState;
+use crate::engine::cancel::CancelToken;
+use crate::engine::guard::{
+    builtin_protected_paths, load_user_protected, save_user_protected, PathGuard, ProtectedPath,
+    ProtectionScope,
+};
+use crate::engine::purge::{
+    execute_purge as run_purge, plan_purge as build_plan, PurgePlan, PurgeReport, StoredPlan,
+    SystemTrash,
+};
+
+/// Plans waiting for the user to confirm them, with the root each was
+/// planned against. Executing a plan removes it, so each plan can run at
+/// most once.
+#[derive(Default)]
+pub struct PurgeState {
+    plans: Mutex<HashMap<String, (String, StoredPlan)>>,
+}
+
+const PROTECTED_PATHS_FILE: &str = "protected_paths.json";
+
+fn protected_paths_file(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    config_file(app, PROTECTED_PATHS_FILE)
+}
+
+fn guard_for_root(app: &tauri::AppHandle, root: Option<&Path>) -> Result<PathGuard, String> {
+    let user_protected = load_user_protected(&protected_paths_file(app)?)?;
+    Ok(PathGuard::new(
+        dirs::home_dir().as_deref(),
+        root,
+        &user_protected,
+    ))
+}
+
+/// Builds the guard for `root`, which must have been scanned, and the
+/// user's protected list.
+pub fn path_guard(
+    app: &tauri::AppHandle,
+    scan_state: &ScanState,
+    root: &str,
+) -> Result<PathGuard, String> {
+    guard_for_root(app, Some(&scan_state.scanned_root(root)?))
+}
+
+/// Builds a guard rooted at the home directory, for cleanups of fixed
+/// locations that do not depend on a scan.
+pub fn home_guard(app: &tauri::AppHandle) -> Result<PathGuard, String> {
+    guard_for_root(app, dirs::home_dir().as_deref())
+}
+
+#[tauri::command]
+pub fn get_protected_paths(app: tauri::AppHandle) -> Result<Vec<ProtectedPath>, String> {
+    let mut protected = builtin_protected_paths(dirs::home_dir().as_deref());
+    let user_protected = load_user_protected(&protected_paths_file(&app)?)?;
+    protected.extend(user_protected.into_iter().map(|user_path| ProtectedPath {
+        path: user_path,
+        scope: ProtectionScope::Subtree,
+        reason: "on your protected list".into(),
+        builtin: false,
+    }));
+    Ok(protected)
+}
+
+#[tauri::command]
+pub fn set_protected_paths(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+) -> Result<Vec<ProtectedPath>, String> {
+    if let Some(relative_path) = paths.iter().find(|path| !PathBuf::from(path).is_absolute()) {
+        return Err(format!("{relative_path} is not an absolute path"));
+    }
+    save_user_protected(&protected_paths_file(&app)?, &paths)?;
+    get_protected_paths(app)
+}
+
+#[tauri::command]
+pub async fn plan_purge(
+    app: tauri::AppHandle,
+    scan_state: tauri::State<'_, ScanState>,
+    purge_state: tauri::State<'_, PurgeState>,
+    root: String,
+    paths: Vec<String>,
+) -> Result<PurgePlan, String> {
+    let guard = path_guard(&app, &scan_state, &root)?;
+    let rules = rule_set(&app)?;
+    let cancel = CancelToken::new();
+    let stored =
+        tauri::async_runtime::spawn_blocking(move || build_plan(&paths, &guard, &rules, &cancel))
+            .await
+            .map_err(|err| format!("Purge planner failed: {err}"))??;
+
+    let plan = stored.plan.clone();
+    purge_state
+        .plans
+        .lock()
+        .map_err(|_| "Purge state is unavailable")?
+        .insert(plan.plan_id.clone(), (root, stored));
+    Ok(plan)
+}
+
+#[tauri::command]
+pub async fn execute_purge(
+    app: tauri::AppHandle,
+    scan_state: tauri::State<'_, ScanState>,
+    purge_state: tauri::State<'_, PurgeState>,
+    plan_id: String,
+) -> Result<PurgeReport, String> {
+    let audit = audit_log(&app)?;
+    let (root, stored) = purge_state
+        .plans
+        .lock()
+        .map_err(|_| "Purge state is unavailable")?
+        .remove(&plan_id)
+        .ok_or_else(|| format!("Unknown or already executed plan {plan_id}"))?;
+    let guard = path_guard(&app, &scan_state, &root)?;
+
+    tauri::async_runtime::spawn_blocking(move || run_purge(&stored, &guard, &SystemTrash, &audit))
+        .await
+        .map_err(|err| format!("Purge worker failed: {err}"))?
+}
@@ -0,0 +1,31 @@
+use super::audit::audit_log;
+use crate::engine::restore::{
+    list_restorable as find_restorable, restore_items as run_restore, RestorableItem,
+    RestoreItemResult,
+};
+
+#[tauri::command]
+pub async fn list_restorable(app: tauri::AppHandle) -> Result<Vec<RestorableItem>, String> {
+    let log = audit_log(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        log.entries().map(|entries| find_restorable(&entries))
+    })
+    .await
+    .map_err(|err| format!("Restore lookup failed: {err}"))?
+}
+
+#[tauri::command]
+pub async fn restore_items(
+    app: tauri::AppHandle,
+    ids: Vec<String>,
+) -> Result<Vec<RestoreItemResult>, String> {
+    if ids.is_empty() {
+        return Err("Nothing selected to restore".into());
+    }
+    let log = audit_log(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        log.entries().map(|entries| run_restore(&ids, &entries))
+    })
+    .await
+    .map_err(|err| format!("Restore worker failed: {err}"))?
+}
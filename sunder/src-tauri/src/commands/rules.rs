@@ -0,0 +1,36 @@
+use super::config_file;
+use crate::engine::category::{list_categories as categories_in, CategoryInfo};
+use crate::engine::rules::{load_user_rules, save_user_rules, user_rules_only, Rule, RuleSet};
+use crate::engine::xdg::platform_layer;
+
+const RULES_FILE: &str = "rules.json";
+
+/// Built-in and platform rules plus the user's rules file.
+pub fn rule_set(app: &tauri::AppHandle) -> Result<RuleSet, String> {
+    let user_rules = load_user_rules(&config_file(app, RULES_FILE)?)?;
+    let home = dirs::home_dir();
+    RuleSet::with_platform(home.as_deref(), user_rules, platform_layer(home.as_deref()))
+}
+
+/// Every rule in the order it is evaluated.
+#[tauri::command]
+pub fn get_rules(app: tauri::AppHandle) -> Result<Vec<Rule>, String> {
+    Ok(rule_set(&app)?.rules())
+}
+
+/// Replaces the user's rules. Built-in rules in `rules` are ignored, and
+/// nothing is saved unless every rule is valid.
+#[tauri::command]
+pub fn set_rules(app: tauri::AppHandle, rules: Vec<Rule>) -> Result<Vec<Rule>, String> {
+    let user_rules = user_rules_only(rules);
+    RuleSet::new(dirs::home_dir().as_deref(), user_rules.clone())?;
+    save_user_rules(&config_file(&app, RULES_FILE)?, &user_rules)?;
+    get_rules(app)
+}
+
+/// Every built-in category plus the custom ones the user's rules introduce.
+#[tauri::command]
+pub fn list_categories(app: tauri::AppHandle) -> Result<Vec<CategoryInfo>, String> {
+    let rules = rule_set(&app)?.rules();
+    Ok(categories_in(rules.iter().map(|rule| &rule.category)))
+}
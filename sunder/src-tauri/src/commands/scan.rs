@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::rules::rule_set;
+use super::snapshots::snapshot_store;
+use crate::engine::cancel::CancelToken;
+use crate::engine::scan::{scan_root, validate_root, ScanOptions, ScanResult};
+use crate::engine::snapshot::SnapshotStore;
+
+/// Holds the token shared by every scan that is running, and the roots
+/// scanned so far. Cancelling swaps in a fresh token, so all running scans
+/// stop and an earlier cancel never leaks into a later scan.
+#[derive(Default)]
+pub struct ScanState {
+    cancel: Mutex<CancelToken>,
+    roots: Mutex<HashSet<PathBuf>>,
+}
+
+impl ScanState {
+    pub fn start(&self, root: &Path) -> CancelToken {
+        if let Ok(mut roots) = self.roots.lock() {
+            roots.insert(root.to_path_buf());
+        }
+        self.cancel_token()
+    }
+
+    /// The shared token, for scans of fixed places that have no root the
+    /// user could later act on.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel
+            .lock()
+            .map(|current| current.clone())
+            .unwrap_or_default()
+    }
+
+    fn cancel_all(&self) {
+        if let Ok(mut current) = self.cancel.lock() {
+            current.cancel();
+            *current = CancelToken::new();
+        }
+    }
+
+    /// `root` if a scan was started on it. Destructive commands are confined
+    /// to the root the user reviewed them against, which must be one of
+    /// these.
+    pub fn scanned_root(&self, root: &str) -> Result<PathBuf, String> {
+        let root_path = PathBuf::from(root);
+        let scanned = self
+            .roots
+            .lock()
+            .map(|roots| roots.contains(&root_path))
+            .unwrap_or(false);
+        if !scanned {
+            return Err(format!("{root} has not been scanned"));
+        }
+        Ok(root_path)
+    }
+}
+
+/// Saves a finished scan so later scans can be compared with it. A scan
+/// that cannot be saved is still returned, without a snapshot id and with
+/// the reason in `snapshot_error`.
+fn with_snapshot(mut result: ScanResult, store: &SnapshotStore) -> ScanResult {
+    if result.cancelled {
+        return result;
+    }
+    match store.save(&result) {
+        Ok(summary) => {
+            result.snapshot_id = Some(summary.id);
+            result.snapshot_error = summary.warning;
+        }
+        Err(save_error) => result.snapshot_error = Some(save_error),
+    }
+    result
+}
+
+#[tauri::command]
+pub fn get_home_dir() -> Result<String, String> {
+    dirs::home_dir()
+        .map(|home_path| home_path.to_string_lossy().to_string())
+        .ok_or_else(|| "Could not resolve home directory".into())
+}
+
+#[tauri::command]
+pub async fn smart_scan(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: tauri::State<'_, ScanState>,
+    options: Option<ScanOptions>,
+) -> Result<ScanResult, String> {
+    let home = dirs::home_dir().ok_or("Could not resolve home directory")?;
+    let options = options.unwrap_or_default();
+    let rules = rule_set(&app)?;
+    let store = snapshot_store(&app)?;
+    let cancel = state.start(&home);
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_root(&home, &options, &rules, &window, &cancel)
+            .map(|result| with_snapshot(result, &store))
+    })
+    .await
+    .map_err(|err| format!("Scan worker failed: {err}"))?
+}
+
+#[tauri::command]
+pub async fn scan_path(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: tauri::State<'_, ScanState>,
+    root: String,
+    options: Option<ScanOptions>,
+) -> Result<ScanResult, String> {
+    let root_path = validate_root(&root)?;
+    let options = options.unwrap_or_default();
+    let rules = rule_set(&app)?;
+    let store = snapshot_store(&app)?;
+    let cancel = state.start(&root_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_root(&root_path, &options, &rules, &window, &cancel)
+            .map(|result| with_snapshot(result, &store))
+    })
+    .await
+    .map_err(|err| format!("Scan worker failed: {err}"))?
+}
+
+#[tauri::command]
+pub fn cancel_scan(state: tauri::State<'_, ScanState>) {
+    state.cancel_all();
+}
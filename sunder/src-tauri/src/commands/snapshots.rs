@@ -0,0 +1,42 @@
+use tauri::Manager;
+
+use crate::engine::crawler::SizeMode;
+use crate::engine::snapshot::{
+    diff_snapshots, ScanDiff, SnapshotStore, SnapshotSummary, SNAPSHOT_DIR,
+};
+
+/// The scan snapshot store under the app data dir.
+pub fn snapshot_store(app: &tauri::AppHandle) -> Result<SnapshotStore, String> {
+    app.path()
+        .app_data_dir()
+        .map(|data_dir| SnapshotStore::new(data_dir.join(SNAPSHOT_DIR)))
+        .map_err(|err| format!("Could not resolve data directory: {err}"))
+}
+
+#[tauri::command]
+pub async fn list_snapshots(app: tauri::AppHandle) -> Result<Vec<SnapshotSummary>, String> {
+    let store = snapshot_store(&app)?;
+    tauri::async_runtime::spawn_blocking(move || store.list())
+        .await
+        .map_err(|err| format!("Snapshot reader failed: {err}"))?
+}
+
+/// How every folder changed from snapshot `a` to snapshot `b`.
+#[tauri::command]
+pub async fn diff_scans(
+    app: tauri::AppHandle,
+    a: String,
+    b: String,
+    size_mode: Option<SizeMode>,
+) -> Result<ScanDiff, String> {
+    let store = snapshot_store(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        diff_snapshots(
+            &store.load(&a)?,
+            &store.load(&b)?,
+            size_mode.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|err| format!("Snapshot diff failed: {err}"))?
+}
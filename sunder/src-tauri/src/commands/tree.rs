@@ -0,0 +1,71 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::scan::ScanState;
+use crate::engine::scan::validate_root;
+use crate::engine::tree::{build_tree, ScanTree, ScannedTree, SizeNode, TreeOptions};
+
+/// The most recent fully retained tree. `expand_node` reads from it so
+/// drilling down never rescans the disk.
+#[derive(Default)]
+pub struct TreeState {
+    tree: Mutex<Option<ScannedTree>>,
+}
+
+#[tauri::command]
+pub async fn scan_tree(
+    window: tauri::Window,
+    scan_state: tauri::State<'_, ScanState>,
+    tree_state: tauri::State<'_, TreeState>,
+    root: String,
+    options: Option<TreeOptions>,
+) -> Result<ScanTree, String> {
+    let root_path = validate_root(&root)?;
+    let options = options.unwrap_or_default();
+    let cancel = scan_state.start(&root_path);
+
+    let worker_cancel = cancel.clone();
+    let worker_options = options.clone();
+    let scanned = tauri::async_runtime::spawn_blocking(move || {
+        build_tree(&root_path, &worker_options, &window, &worker_cancel)
+    })
+    .await
+    .map_err(|err| format!("Scan worker failed: {err}"))??;
+
+    let root_view = scanned
+        .view(&scanned.root_path, &options)
+        .ok_or("Scanned tree has no root")?;
+    let skipped_mounts = scanned.skipped_mounts.clone();
+    let errors = scanned.errors.clone();
+
+    let mut stored_tree = tree_state
+        .tree
+        .lock()
+        .map_err(|_| "Tree state is unavailable")?;
+    *stored_tree = Some(scanned);
+
+    Ok(ScanTree {
+        root: root_view,
+        skipped_mounts,
+        errors,
+        cancelled: cancel.is_cancelled(),
+    })
+}
+
+#[tauri::command]
+pub fn expand_node(
+    tree_state: tauri::State<'_, TreeState>,
+    path: String,
+    options: Option<TreeOptions>,
+) -> Result<SizeNode, String> {
+    let options = options.unwrap_or_default();
+    let stored_tree = tree_state
+        .tree
+        .lock()
+        .map_err(|_| "Tree state is unavailable")?;
+    let scanned = stored_tree.as_ref().ok_or("No tree has been scanned yet")?;
+
+    scanned
+        .view(Path::new(&path), &options)
+        .ok_or_else(|| format!("{path} is not part of the scanned tree"))
+}
@@ -0,0 +1,172 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+use super::artifacts::is_generated_dir;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivitySource {
+    /// The newest commit touching the project.
+    GitCommit,
+    /// The newest modification time of a file outside build artifacts.
+    FileMtime,
+}
+
+/// When a project was last worked on, and which signal that came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProjectActivity {
+    /// Seconds since the Unix epoch.
+    pub last_active: u64,
+    pub source: ActivitySource,
+}
+
+impl ProjectActivity {
+    /// Whole days between the last activity and `now`.
+    pub fn idle_days(&self, now: u64) -> u64 {
+        now.saturating_sub(self.last_active) / SECONDS_PER_DAY
+    }
+}
+
+pub fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// The time of the last commit that touched `project`, when it sits in a
+/// git work tree and `git` is installed.
+fn git_commit_time(project: &Path) -> Option<u64> {
+    project.ancestors().find(|dir| dir.join(".git").exists())?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project)
+        .args(["log", "-1", "--format=%ct", "--", "."])
+        // A read-only query; never take locks another git process may want.
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()?.trim().parse().ok()
+}
+
+/// The newest mtime of any file in `project`, ignoring build artifacts and
+/// version control metadata so rebuilding does not make a project look
+/// active.
+fn newest_source_mtime(project: &Path) -> Option<u64> {
+    WalkDir::new(project)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !is_generated_dir(entry.path(), &entry.file_name().to_string_lossy())
+        })
+        .filter_map(|entry_result| entry_result.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .max()
+        .map(epoch_seconds)
+}
+
+/// The later of the last commit and the newest source file, so uncommitted
+/// work keeps a project active and so does history newer than checkout
+/// times. A tie goes to the commit.
+pub fn project_activity(project: &Path) -> Option<ProjectActivity> {
+    let committed = git_commit_time(project).map(|last_active| ProjectActivity {
+        last_active,
+        source: ActivitySource::GitCommit,
+    });
+    let edited = newest_source_mtime(project).map(|last_active| ProjectActivity {
+        last_active,
+        source: ActivitySource::FileMtime,
+    });
+    match (committed, edited) {
+        (Some(committed), Some(edited)) if edited.last_active > committed.last_active => {
+            Some(edited)
+        }
+        (Some(committed), _) => Some(committed),
+        (None, edited) => edited,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::time::Duration;
+
+    fn write_file_aged(path: &Path, days_old: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"x").unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(days_old * SECONDS_PER_DAY);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn now() -> u64 {
+        epoch_seconds(SystemTime::now())
+    }
+
+    #[test]
+    fn uses_the_newest_source_file_and_ignores_artifacts() {
+        let project = tempfile::tempdir().unwrap();
+        write_file_aged(&project.path().join("Cargo.toml"), 200);
+        write_file_aged(&project.path().join("src/main.rs"), 90);
+        write_file_aged(&project.path().join("target/debug/app"), 0);
+
+        let activity = project_activity(project.path()).unwrap();
+        assert_eq!(activity.source, ActivitySource::FileMtime);
+        assert_eq!(activity.idle_days(now()), 90);
+    }
+
+    #[test]
+    fn empty_projects_have_no_activity() {
+        let project = tempfile::tempdir().unwrap();
+        assert_eq!(project_activity(project.path()), None);
+    }
+
+    #[test]
+    fn uses_the_later_of_commit_time_and_source_edits() {
+        let repo = tempfile::tempdir().unwrap();
+        // Older than the commit below, which is dated September 2020.
+        write_file_aged(&repo.path().join("app/package.json"), 10_000);
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(repo.path())
+                .args(args)
+                .env("GIT_AUTHOR_NAME", "test")
+                .env("GIT_AUTHOR_EMAIL", "test@example.com")
+                .env("GIT_COMMITTER_NAME", "test")
+                .env("GIT_COMMITTER_EMAIL", "test@example.com")
+                .env("GIT_COMMITTER_DATE", "@1600000000 +0000")
+                .output()
+                .is_ok_and(|output| output.status.success())
+        };
+        // Without git installed there is nothing to compare against.
+        if !git(&["init", "-q"]) || !git(&["add", "."]) || !git(&["commit", "-qm", "init"]) {
+            return;
+        }
+
+        let activity = project_activity(&repo.path().join("app")).unwrap();
+        assert_eq!(activity.source, ActivitySource::GitCommit);
+        assert_eq!(activity.last_active, 1_600_000_000);
+
+        // Uncommitted work is newer than the last commit.
+        write_file_aged(&repo.path().join("app/src/index.js"), 3);
+        let activity = project_activity(&repo.path().join("app")).unwrap();
+        assert_eq!(activity.source, ActivitySource::FileMtime);
+        assert_eq!(activity.idle_days(now()), 3);
+    }
+}
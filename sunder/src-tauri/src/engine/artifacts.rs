@@ -0,0 +1,495 @@
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use super::activity::{epoch_seconds, project_activity, ActivitySource, ProjectActivity};
+use super::cancel::CancelToken;
+use super::crawler::{walk_dir, SizeMode, WalkContext};
+use super::errors::ScanError;
+use super::mounts::SkippedMount;
+use super::progress::{FolderProgress, ProgressSink};
+
+// -- Artifact kinds --
+
+/// Where the confirming marker file has to be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MarkerPlace {
+    /// Next to the artifact, in the project directory (`Cargo.toml`).
+    Beside,
+    /// Inside the artifact itself (`pyvenv.cfg`).
+    Inside,
+}
+
+/// A build output or dependency folder, recognised by its name and confirmed
+/// by a marker file so an unrelated `build` folder is never flagged.
+struct ArtifactKind {
+    dir_names: &'static [&'static str],
+    markers: &'static [&'static str],
+    marker_place: MarkerPlace,
+    ecosystem: &'static str,
+}
+
+const ARTIFACT_KINDS: &[ArtifactKind] = &[
+    ArtifactKind {
+        dir_names: &["target"],
+        markers: &["Cargo.toml"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Rust",
+    },
+    ArtifactKind {
+        dir_names: &["target"],
+        markers: &["pom.xml"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Maven",
+    },
+    ArtifactKind {
+        dir_names: &["node_modules"],
+        markers: &["package.json"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Node.js",
+    },
+    ArtifactKind {
+        dir_names: &[".next"],
+        markers: &["next.config.js", "next.config.mjs", "next.config.ts"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Next.js",
+    },
+    ArtifactKind {
+        dir_names: &[".venv", "venv"],
+        markers: &["pyvenv.cfg"],
+        marker_place: MarkerPlace::Inside,
+        ecosystem: "Python",
+    },
+    ArtifactKind {
+        dir_names: &[".tox"],
+        markers: &["tox.ini"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Python",
+    },
+    ArtifactKind {
+        dir_names: &["build", ".gradle"],
+        markers: &[
+            "build.gradle",
+            "build.gradle.kts",
+            "settings.gradle",
+            "settings.gradle.kts",
+        ],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Gradle",
+    },
+    ArtifactKind {
+        dir_names: &["build", "cmake-build-debug", "cmake-build-release"],
+        markers: &["CMakeCache.txt"],
+        marker_place: MarkerPlace::Inside,
+        ecosystem: "CMake",
+    },
+    ArtifactKind {
+        dir_names: &[".build"],
+        markers: &["Package.swift"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Swift",
+    },
+    ArtifactKind {
+        dir_names: &[".dart_tool", "build"],
+        markers: &["pubspec.yaml"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Dart",
+    },
+    ArtifactKind {
+        dir_names: &["Pods"],
+        markers: &["Podfile"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "CocoaPods",
+    },
+    ArtifactKind {
+        dir_names: &["_build", "deps"],
+        markers: &["mix.exs"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Elixir",
+    },
+    ArtifactKind {
+        dir_names: &[".zig-cache", "zig-cache", "zig-out"],
+        markers: &["build.zig"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Zig",
+    },
+    ArtifactKind {
+        dir_names: &[".stack-work"],
+        markers: &["stack.yaml"],
+        marker_place: MarkerPlace::Beside,
+        ecosystem: "Haskell",
+    },
+];
+
+/// Directories never descended into while looking for artifacts.
+const SKIPPED_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// The first kind `dir` is an instance of, with the marker that proved it.
+fn match_artifact(dir: &Path, name: &str) -> Option<(&'static ArtifactKind, &'static str)> {
+    ARTIFACT_KINDS
+        .iter()
+        .filter(|kind| kind.dir_names.contains(&name))
+        .find_map(|kind| {
+            let marker_dir = match kind.marker_place {
+                MarkerPlace::Beside => dir.parent()?,
+                MarkerPlace::Inside => dir,
+            };
+            kind.markers
+                .iter()
+                .find(|marker| marker_dir.join(marker).is_file())
+                .map(|marker| (kind, *marker))
+        })
+}
+
+/// Build output or version control metadata: never evidence that a
+/// project is being worked on.
+pub(super) fn is_generated_dir(dir: &Path, name: &str) -> bool {
+    SKIPPED_DIRS.contains(&name) || match_artifact(dir, name).is_some()
+}
+
+// -- Shared types --
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct ArtifactOptions {
+    pub size_mode: SizeMode,
+    pub same_filesystem: bool,
+    /// Only report artifacts whose project has been idle for at least this
+    /// many days. Projects whose activity cannot be determined are left out
+    /// when this is set.
+    pub stale_days: Option<u64>,
+}
+
+impl Default for ArtifactOptions {
+    fn default() -> Self {
+        Self {
+            size_mode: SizeMode::default(),
+            same_filesystem: true,
+            stale_days: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DevArtifact {
+    pub name: String,
+    pub path: String,
+    /// The project directory the artifact belongs to.
+    pub project_path: String,
+    pub ecosystem: String,
+    /// The file that confirmed the match, e.g. `Cargo.toml`.
+    pub marker: String,
+    pub size_bytes: u64,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    pub file_count: u64,
+    /// When the owning project was last worked on, in seconds since the
+    /// Unix epoch. `None` when nothing in the project says.
+    pub last_activity: Option<u64>,
+    pub activity_source: Option<ActivitySource>,
+    pub idle_days: Option<u64>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ArtifactScan {
+    pub root: String,
+    pub size_mode: SizeMode,
+    /// Largest first.
+    pub artifacts: Vec<DevArtifact>,
+    pub total_size_bytes: u64,
+    pub skipped_mounts: Vec<SkippedMount>,
+    pub errors: Vec<ScanError>,
+    pub cancelled: bool,
+}
+
+// -- Scan --
+
+struct Found {
+    path: PathBuf,
+    kind: &'static ArtifactKind,
+    marker: &'static str,
+}
+
+impl Found {
+    fn project(&self) -> &Path {
+        self.path.parent().unwrap_or(&self.path)
+    }
+}
+
+/// Walks the whole tree looking for artifacts. A matched artifact is not
+/// descended into, so nested `node_modules` are counted once, as part of
+/// the outermost one.
+fn discover(root: &Path, context: &WalkContext) -> Vec<Found> {
+    let mut found = Vec::new();
+    let mut walker = WalkDir::new(root).follow_links(false).into_iter();
+
+    while let Some(entry_result) = walker.next() {
+        if context.is_cancelled() {
+            break;
+        }
+        let entry = match entry_result {
+            Ok(entry) => entry,
+            Err(walk_error) => {
+                if let (Some(path), Some(io_error)) = (walk_error.path(), walk_error.io_error()) {
+                    context.record_error(path, io_error);
+                }
+                continue;
+            }
+        };
+        if !entry.file_type().is_dir() || entry.depth() == 0 {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy();
+        if SKIPPED_DIRS.contains(&name.as_ref()) || context.crosses_mount(entry.path()) {
+            walker.skip_current_dir();
+            continue;
+        }
+        if let Some((kind, marker)) = match_artifact(entry.path(), &name) {
+            found.push(Found {
+                path: entry.path().to_path_buf(),
+                kind,
+                marker,
+            });
+            walker.skip_current_dir();
+        }
+    }
+    found
+}
+
+/// Finds build artifacts anywhere below `root`, confirms each one by its
+/// marker file and sizes them in parallel.
+pub fn scan_artifacts(
+    root: &Path,
+    options: &ArtifactOptions,
+    sink: &dyn ProgressSink,
+    cancel: &CancelToken,
+) -> ArtifactScan {
+    let mut context = WalkContext::new(cancel);
+    if options.same_filesystem {
+        context = context.same_filesystem_as(root);
+    }
+
+    let found = discover(root, &context);
+
+    // Several artifacts can share a project; look each project up once.
+    let mut projects: Vec<PathBuf> = found
+        .iter()
+        .map(|artifact| artifact.project().to_path_buf())
+        .collect();
+    projects.sort();
+    projects.dedup();
+    let activities: HashMap<PathBuf, ProjectActivity> = projects
+        .into_par_iter()
+        .filter(|_| !cancel.is_cancelled())
+        .filter_map(|project| {
+            let activity = project_activity(&project)?;
+            Some((project, activity))
+        })
+        .collect();
+
+    let now = epoch_seconds(SystemTime::now());
+    let found: Vec<(Found, Option<ProjectActivity>)> = found
+        .into_iter()
+        .map(|artifact| {
+            let activity = activities.get(artifact.project()).copied();
+            (artifact, activity)
+        })
+        .filter(|(_, activity)| {
+            options.stale_days.is_none_or(|stale_days| {
+                activity.is_some_and(|activity| activity.idle_days(now) >= stale_days)
+            })
+        })
+        .collect();
+    let progress = FolderProgress::start(sink, found.len() as u64);
+
+    let mut artifacts: Vec<DevArtifact> = found
+        .into_par_iter()
+        .filter_map(|(artifact, activity)| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let name = artifact
+                .path
+                .file_name()
+                .map(|os_name| os_name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let node = walk_dir(&artifact.path, &context);
+            progress.folder_done(&name);
+
+            Some(DevArtifact {
+                project_path: artifact.project().to_string_lossy().to_string(),
+                path: artifact.path.to_string_lossy().to_string(),
+                name,
+                ecosystem: artifact.kind.ecosystem.to_string(),
+                marker: artifact.marker.to_string(),
+                size_bytes: node.size_bytes(options.size_mode),
+                apparent_bytes: node.apparent_bytes,
+                allocated_bytes: node.allocated_bytes,
+                file_count: node.file_count,
+                last_activity: activity.map(|activity| activity.last_active),
+                activity_source: activity.map(|activity| activity.source),
+                idle_days: activity.map(|activity| activity.idle_days(now)),
+            })
+        })
+        .collect();
+
+    let cancelled = cancel.is_cancelled();
+    if !cancelled {
+        progress.finish();
+    }
+
+    artifacts.sort_by_key(|artifact| std::cmp::Reverse(artifact.size_bytes));
+    ArtifactScan {
+        root: root.to_string_lossy().to_string(),
+        size_mode: options.size_mode,
+        total_size_bytes: artifacts.iter().map(|artifact| artifact.size_bytes).sum(),
+        artifacts,
+        skipped_mounts: context.skipped_mounts(),
+        errors: context.scan_errors(),
+        cancelled,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::progress::NoopSink;
+    use crate::engine::test_support::write_file;
+    use std::fs;
+
+    fn scan(root: &Path) -> ArtifactScan {
+        scan_artifacts(
+            root,
+            &ArtifactOptions::default(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+    }
+
+    fn found_paths(result: &ArtifactScan, root: &Path) -> Vec<String> {
+        let mut paths: Vec<String> = result
+            .artifacts
+            .iter()
+            .map(|artifact| {
+                Path::new(&artifact.path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn finds_artifacts_confirmed_by_their_markers() {
+        let code = tempfile::tempdir().unwrap();
+        write_file(&code.path().join("cli/Cargo.toml"), 10);
+        write_file(&code.path().join("cli/target/debug/cli"), 5000);
+        write_file(&code.path().join("web/package.json"), 10);
+        write_file(&code.path().join("web/node_modules/react/index.js"), 800);
+        write_file(&code.path().join("ml/.venv/pyvenv.cfg"), 10);
+        write_file(&code.path().join("ml/.venv/lib/torch.so"), 3000);
+        write_file(&code.path().join("android/build.gradle.kts"), 10);
+        write_file(&code.path().join("android/build/outputs/app.apk"), 700);
+
+        let result = scan(code.path());
+        assert_eq!(
+            found_paths(&result, code.path()),
+            [
+                "android/build",
+                "cli/target",
+                "ml/.venv",
+                "web/node_modules"
+            ]
+        );
+        assert_eq!(result.artifacts[0].ecosystem, "Rust");
+        assert_eq!(result.artifacts[0].marker, "Cargo.toml");
+        assert_eq!(result.artifacts[0].size_bytes, 5000);
+        assert_eq!(result.total_size_bytes, 5000 + 800 + 3010 + 700);
+    }
+
+    #[test]
+    fn unconfirmed_names_are_not_flagged() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join("notes/build/plan.md"), 100);
+        write_file(&home.path().join("archery/target/scores.csv"), 100);
+        write_file(&home.path().join("old/venv/readme.txt"), 100);
+
+        assert!(scan(home.path()).artifacts.is_empty());
+    }
+
+    #[test]
+    fn nested_artifacts_are_counted_once() {
+        let code = tempfile::tempdir().unwrap();
+        write_file(&code.path().join("app/package.json"), 10);
+        write_file(&code.path().join("app/node_modules/a/package.json"), 10);
+        write_file(
+            &code
+                .path()
+                .join("app/node_modules/a/node_modules/b/index.js"),
+            90,
+        );
+
+        let result = scan(code.path());
+        assert_eq!(found_paths(&result, code.path()), ["app/node_modules"]);
+        assert_eq!(result.artifacts[0].size_bytes, 100);
+    }
+
+    #[test]
+    fn version_control_dirs_are_skipped() {
+        let code = tempfile::tempdir().unwrap();
+        write_file(&code.path().join("repo/.git/modules/dep/Cargo.toml"), 10);
+        write_file(&code.path().join("repo/.git/modules/dep/target/x"), 10);
+
+        assert!(scan(code.path()).artifacts.is_empty());
+    }
+
+    #[test]
+    fn stale_filter_keeps_only_idle_projects() {
+        let code = tempfile::tempdir().unwrap();
+        write_file(&code.path().join("old/Cargo.toml"), 10);
+        write_file(&code.path().join("old/target/debug/old"), 100);
+        write_file(&code.path().join("fresh/Cargo.toml"), 10);
+        write_file(&code.path().join("fresh/target/debug/fresh"), 100);
+        let long_ago = SystemTime::now() - std::time::Duration::from_secs(400 * 24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(code.path().join("old/Cargo.toml"))
+            .unwrap()
+            .set_modified(long_ago)
+            .unwrap();
+
+        let everything = scan(code.path());
+        assert_eq!(everything.artifacts.len(), 2);
+        assert!(everything
+            .artifacts
+            .iter()
+            .all(|artifact| artifact.activity_source == Some(ActivitySource::FileMtime)));
+
+        let options = ArtifactOptions {
+            stale_days: Some(180),
+            ..ArtifactOptions::default()
+        };
+        let stale = scan_artifacts(code.path(), &options, &NoopSink, &CancelToken::new());
+        assert_eq!(found_paths(&stale, code.path()), ["old/target"]);
+        assert_eq!(stale.artifacts[0].idle_days, Some(400));
+    }
+
+    #[test]
+    fn cancelled_scan_is_flagged() {
+        let code = tempfile::tempdir().unwrap();
+        write_file(&code.path().join("cli/Cargo.toml"), 10);
+        write_file(&code.path().join("cli/target/debug/cli"), 5000);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let result = scan_artifacts(code.path(), &ArtifactOptions::default(), &NoopSink, &cancel);
+        assert!(result.cancelled);
+        assert!(result.artifacts.is_empty());
+    }
+}
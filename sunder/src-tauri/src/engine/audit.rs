@@ -0,0 +1,313 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::category::Category;
+use super::purge::{PurgeItemResult, PurgeOutcome};
+
+pub const AUDIT_LOG_FILE: &str = "audit.jsonl";
+
+/// One line of the audit log: a single path a purge tried to remove.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AuditEntry {
+    /// `<plan id>/<index>`, unique across the log.
+    pub id: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub plan_id: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub category: Category,
+    pub outcome: PurgeOutcome,
+    pub error: Option<String>,
+    /// Where the item now lives inside the trash, when the platform says.
+    pub trash_location: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(plan_id: &str, index: usize, timestamp: u64, item: &PurgeItemResult) -> Self {
+        Self {
+            id: format!("{plan_id}/{index}"),
+            timestamp,
+            plan_id: plan_id.to_string(),
+            path: item.path.clone(),
+            size_bytes: item.size_bytes,
+            category: item.category.clone(),
+            outcome: item.outcome,
+            error: item.error.clone(),
+            trash_location: item.trash_location.clone(),
+        }
+    }
+}
+
+/// Filters and paging for reading the log. Every filter is optional.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub offset: usize,
+    pub limit: usize,
+    pub plan_id: Option<String>,
+    pub outcome: Option<PurgeOutcome>,
+    /// Case-sensitive substring of the purged path.
+    pub path_contains: Option<String>,
+    /// Inclusive bounds on `timestamp`.
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
+
+impl Default for AuditQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: 100,
+            plan_id: None,
+            outcome: None,
+            path_contains: None,
+            since: None,
+            until: None,
+        }
+    }
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.plan_id
+            .as_ref()
+            .is_none_or(|plan_id| &entry.plan_id == plan_id)
+            && self.outcome.is_none_or(|outcome| entry.outcome == outcome)
+            && self
+                .path_contains
+                .as_ref()
+                .is_none_or(|needle| entry.path.contains(needle.as_str()))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AuditPage {
+    /// Matching entries, newest first.
+    pub entries: Vec<AuditEntry>,
+    /// How many entries matched before paging.
+    pub total: usize,
+    pub offset: usize,
+}
+
+/// Append-only JSON-lines file. Lines are never rewritten; a torn final line
+/// left by a crash is skipped when reading.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the log for appending, creating it and its directory if needed.
+    /// Purges open the writer before touching anything, so an unwritable log
+    /// stops the purge instead of leaving it unrecorded.
+    pub fn writer(&self) -> Result<AuditWriter, String> {
+        let log_error =
+            |io_error: std::io::Error| format!("Audit log {}: {io_error}", self.path.display());
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(log_error)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(log_error)?;
+
+        // Close off a torn line so the next entry starts on its own line.
+        if file.metadata().map_err(log_error)?.len() > 0 {
+            let mut last_byte = [0_u8];
+            file.seek(SeekFrom::End(-1))
+                .and_then(|_| file.read_exact(&mut last_byte))
+                .map_err(log_error)?;
+            if last_byte[0] != b'\n' {
+                file.write_all(b"\n").map_err(log_error)?;
+            }
+        }
+        Ok(AuditWriter { file })
+    }
+
+    /// Every readable entry in the order it was written. A missing log is an
+    /// empty one.
+    pub fn entries(&self) -> Result<Vec<AuditEntry>, String> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(open_error) if open_error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(open_error) => {
+                return Err(format!("Audit log {}: {open_error}", self.path.display()))
+            }
+        };
+
+        Ok(BufReader::new(file)
+            .lines()
+            .map_while(|line_result| line_result.ok())
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect())
+    }
+
+    pub fn read(&self, query: &AuditQuery) -> Result<AuditPage, String> {
+        let matching: Vec<AuditEntry> = self
+            .entries()?
+            .into_iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .collect();
+
+        Ok(AuditPage {
+            total: matching.len(),
+            entries: matching
+                .into_iter()
+                .skip(query.offset)
+                .take(query.limit)
+                .collect(),
+            offset: query.offset,
+        })
+    }
+}
+
+pub struct AuditWriter {
+    file: File,
+}
+
+impl AuditWriter {
+    /// Writes one entry as a single line and flushes it to disk before
+    /// returning.
+    pub fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+        let mut line = serde_json::to_string(entry).map_err(|json_error| json_error.to_string())?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|()| self.file.sync_data())
+            .map_err(|io_error| format!("Audit log write failed: {io_error}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(plan_id: &str, index: usize, timestamp: u64, outcome: PurgeOutcome) -> AuditEntry {
+        AuditEntry {
+            id: format!("{plan_id}/{index}"),
+            timestamp,
+            plan_id: plan_id.into(),
+            path: format!("/home/me/cache-{index}"),
+            size_bytes: 10,
+            category: Category::PackageCaches,
+            outcome,
+            error: None,
+            trash_location: None,
+        }
+    }
+
+    fn log_with(entries: &[AuditEntry]) -> (tempfile::TempDir, AuditLog) {
+        let data_dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(data_dir.path().join("nested").join(AUDIT_LOG_FILE));
+        let mut writer = log.writer().unwrap();
+        for audit_entry in entries {
+            writer.append(audit_entry).unwrap();
+        }
+        (data_dir, log)
+    }
+
+    #[test]
+    fn missing_log_reads_as_empty() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let page = AuditLog::new(data_dir.path().join(AUDIT_LOG_FILE))
+            .read(&AuditQuery::default())
+            .unwrap();
+        assert_eq!(page.total, 0);
+        assert!(page.entries.is_empty());
+    }
+
+    #[test]
+    fn appends_survive_reopening_and_read_newest_first() {
+        let (_data_dir, log) = log_with(&[entry("plan-a", 0, 100, PurgeOutcome::Trashed)]);
+        log.writer()
+            .unwrap()
+            .append(&entry("plan-b", 0, 200, PurgeOutcome::Trashed))
+            .unwrap();
+
+        let page = log.read(&AuditQuery::default()).unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.entries[0].id, "plan-b/0");
+        assert_eq!(page.entries[1].id, "plan-a/0");
+    }
+
+    #[test]
+    fn pages_and_filters() {
+        let (_data_dir, log) = log_with(&[
+            entry("plan-a", 0, 100, PurgeOutcome::Trashed),
+            entry("plan-a", 1, 100, PurgeOutcome::Failed),
+            entry("plan-b", 0, 200, PurgeOutcome::Trashed),
+            entry("plan-c", 0, 300, PurgeOutcome::Trashed),
+        ]);
+
+        let second_page = log
+            .read(&AuditQuery {
+                offset: 1,
+                limit: 2,
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert_eq!(second_page.total, 4);
+        let ids: Vec<&str> = second_page.entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["plan-b/0", "plan-a/1"]);
+
+        let failed = log
+            .read(&AuditQuery {
+                outcome: Some(PurgeOutcome::Failed),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert_eq!(failed.total, 1);
+
+        let windowed = log
+            .read(&AuditQuery {
+                since: Some(150),
+                until: Some(250),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert_eq!(windowed.entries[0].plan_id, "plan-b");
+
+        let by_plan = log
+            .read(&AuditQuery {
+                plan_id: Some("plan-a".into()),
+                path_contains: Some("cache-1".into()),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert_eq!(by_plan.total, 1);
+    }
+
+    #[test]
+    fn torn_lines_are_skipped() {
+        let (_data_dir, log) = log_with(&[entry("plan-a", 0, 100, PurgeOutcome::Trashed)]);
+        let mut raw = OpenOptions::new().append(true).open(log.path()).unwrap();
+        raw.write_all(b"{\"id\":\"plan-b/0\",\"times").unwrap();
+
+        let page = log.read(&AuditQuery::default()).unwrap();
+        assert_eq!(page.total, 1);
+
+        log.writer()
+            .unwrap()
+            .append(&entry("plan-c", 0, 300, PurgeOutcome::Trashed))
+            .unwrap();
+        let page = log.read(&AuditQuery::default()).unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.entries[0].id, "plan-c/0");
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag that long-running engine work polls between entries.
+/// Clones share the same flag, so the command layer can keep one copy and
+/// hand another to the scan worker.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_observe_cancellation() {
+        let token = CancelToken::new();
+        let worker_copy = token.clone();
+        assert!(!worker_copy.is_cancelled());
+
+        token.cancel();
+        assert!(worker_copy.is_cancelled());
+    }
+}
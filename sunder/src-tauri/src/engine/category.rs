@@ -0,0 +1,261 @@
+use std::fmt;
+
+use super::rules::SafetyLevel;
+
+/// What a folder is, as decided by the rules. Built-in categories serialize
+/// as stable snake_case ids; categories invented by user rules serialize as
+/// whatever name the rule gave them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    VirtualMachines,
+    PackageCaches,
+    DeveloperCaches,
+    BuildArtifacts,
+    Caches,
+    SystemLibraries,
+    Applications,
+    ApplicationData,
+    ApplicationSettings,
+    Games,
+    Trash,
+    UserFiles,
+    Other,
+    /// A category only user rules use.
+    Custom(String),
+}
+
+/// A category as the frontend lists it.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct CategoryInfo {
+    pub id: String,
+    pub name: String,
+    /// How safe folders in this category usually are to delete. Rules can
+    /// rate individual folders differently.
+    pub safety: SafetyLevel,
+    pub description: String,
+    pub builtin: bool,
+}
+
+struct BuiltinDetails {
+    id: &'static str,
+    name: &'static str,
+    safety: SafetyLevel,
+    description: &'static str,
+}
+
+impl Category {
+    pub const BUILTIN: &'static [Category] = &[
+        Category::VirtualMachines,
+        Category::PackageCaches,
+        Category::DeveloperCaches,
+        Category::BuildArtifacts,
+        Category::Caches,
+        Category::SystemLibraries,
+        Category::Applications,
+        Category::ApplicationData,
+        Category::ApplicationSettings,
+        Category::Games,
+        Category::Trash,
+        Category::UserFiles,
+        Category::Other,
+    ];
+
+    fn details(&self) -> Option<BuiltinDetails> {
+        let (id, name, safety, description) = match self {
+            Category::VirtualMachines => (
+                "virtual_machines",
+                "Virtual Machines & Containers",
+                SafetyLevel::Caution,
+                "Container and VM disk images",
+            ),
+            Category::PackageCaches => (
+                "package_caches",
+                "Package Caches",
+                SafetyLevel::Regenerable,
+                "Downloaded and installed packages",
+            ),
+            Category::DeveloperCaches => (
+                "developer_caches",
+                "Developer Caches",
+                SafetyLevel::Regenerable,
+                "Caches kept by compilers, build tools and container engines",
+            ),
+            Category::BuildArtifacts => (
+                "build_artifacts",
+                "Build Artifacts",
+                SafetyLevel::Regenerable,
+                "Compiler output",
+            ),
+            Category::Caches => (
+                "caches",
+                "Caches",
+                SafetyLevel::Regenerable,
+                "Application caches",
+            ),
+            Category::SystemLibraries => (
+                "system_libraries",
+                "System Libraries",
+                SafetyLevel::Never,
+                "Application support data and system libraries",
+            ),
+            Category::Applications => (
+                "applications",
+                "Applications",
+                SafetyLevel::Caution,
+                "Installed applications and their runtimes",
+            ),
+            Category::ApplicationData => (
+                "application_data",
+                "Application Data",
+                SafetyLevel::Caution,
+                "Data applications keep for themselves",
+            ),
+            Category::ApplicationSettings => (
+                "application_settings",
+                "Application Settings",
+                SafetyLevel::Never,
+                "Application settings",
+            ),
+            Category::Games => ("games", "Games", SafetyLevel::Caution, "Installed games"),
+            Category::Trash => (
+                "trash",
+                "Trash",
+                SafetyLevel::SafeToDelete,
+                "Items already moved to the trash",
+            ),
+            Category::UserFiles => (
+                "user_files",
+                "User Files",
+                SafetyLevel::Never,
+                "Personal files",
+            ),
+            Category::Other => (
+                "other",
+                "Other",
+                SafetyLevel::Caution,
+                "Folders no rule recognizes",
+            ),
+            Category::Custom(_) => return None,
+        };
+        Some(BuiltinDetails {
+            id,
+            name,
+            safety,
+            description,
+        })
+    }
+
+    /// Built-in categories are found by id or by display name, which older
+    /// rules files and audit logs store. Anything else is a custom category.
+    pub fn from_id(id: &str) -> Self {
+        Self::BUILTIN
+            .iter()
+            .find(|builtin| {
+                builtin
+                    .details()
+                    .is_some_and(|details| details.id == id || details.name == id)
+            })
+            .cloned()
+            .unwrap_or_else(|| Category::Custom(id.to_string()))
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Category::Custom(name) => name,
+            builtin => builtin.details().map_or("", |details| details.id),
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        match self {
+            Category::Custom(name) => name,
+            builtin => builtin.details().map_or("", |details| details.name),
+        }
+    }
+
+    pub fn info(&self) -> CategoryInfo {
+        let details = self.details();
+        CategoryInfo {
+            id: self.id().to_string(),
+            name: self.display_name().to_string(),
+            safety: details
+                .as_ref()
+                .map_or(SafetyLevel::Caution, |details| details.safety),
+            description: details
+                .as_ref()
+                .map_or("Defined by your rules", |details| details.description)
+                .to_string(),
+            builtin: details.is_some(),
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.display_name())
+    }
+}
+
+impl serde::Serialize for Category {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Category {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Ok(Category::from_id(&id))
+    }
+}
+
+/// Every built-in category followed by the custom ones `categories` uses,
+/// each listed once.
+pub fn list_categories<'a>(
+    categories: impl IntoIterator<Item = &'a Category>,
+) -> Vec<CategoryInfo> {
+    let mut listed: Vec<Category> = Category::BUILTIN.to_vec();
+    for category in categories {
+        if !listed.contains(category) {
+            listed.push(category.clone());
+        }
+    }
+    listed.iter().map(Category::info).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_round_trip_and_display_names_are_accepted() {
+        for builtin in Category::BUILTIN {
+            let encoded = serde_json::to_string(builtin).unwrap();
+            assert_eq!(encoded, format!("\"{}\"", builtin.id()));
+            assert_eq!(
+                &serde_json::from_str::<Category>(&encoded).unwrap(),
+                builtin
+            );
+        }
+        assert_eq!(
+            serde_json::from_str::<Category>("\"Package Caches\"").unwrap(),
+            Category::PackageCaches
+        );
+        assert_eq!(
+            serde_json::from_str::<Category>("\"Releases\"").unwrap(),
+            Category::Custom("Releases".into())
+        );
+    }
+
+    #[test]
+    fn custom_categories_are_listed_once_after_the_builtin_ones() {
+        let releases = Category::Custom("Releases".into());
+        let listed = list_categories([&releases, &Category::Trash, &releases]);
+        assert_eq!(listed.len(), Category::BUILTIN.len() + 1);
+
+        let last = listed.last().unwrap();
+        assert_eq!(last.id, "Releases");
+        assert!(!last.builtin);
+        assert_eq!(listed[0].name, "Virtual Machines & Containers");
+    }
+}
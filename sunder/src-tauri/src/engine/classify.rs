@@ -0,0 +1,30 @@
+use super::category::Category;
+use super::rules::builtin_category;
+
+// -- Classification --
+
+/// The built-in category for a folder name. Scans classify through a
+/// [`RuleSet`](super::rules::RuleSet) so user rules apply; this is the
+/// name-only view of the same built-in table.
+pub fn classify_folder(name: &str) -> Category {
+    builtin_category(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_names_map_to_their_category() {
+        assert_eq!(classify_folder(".docker"), Category::VirtualMachines);
+        assert_eq!(classify_folder(".cargo"), Category::PackageCaches);
+        assert_eq!(classify_folder("target"), Category::BuildArtifacts);
+        assert_eq!(classify_folder("Documents"), Category::UserFiles);
+    }
+
+    #[test]
+    fn unknown_names_fall_back_to_other() {
+        assert_eq!(classify_folder("projects"), Category::Other);
+        assert_eq!(classify_folder("documents"), Category::Other);
+    }
+}
@@ -0,0 +1,707 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::audit::{AuditEntry, AuditLog};
+use super::cancel::CancelToken;
+use super::category::Category;
+use super::crawler::dir_size;
+use super::guard::PathGuard;
+use super::purge::{next_plan_id, unix_now, PurgeItemResult, PurgeOutcome, Trasher};
+
+/// Stands in for the cache folder in tool arguments and environment values.
+pub const FOLDER_PLACEHOLDER: &str = "{folder}";
+
+/// A safe subdirectory entry meaning the whole folder, for caches that hold
+/// nothing but downloaded or derived data.
+pub const WHOLE_FOLDER: &str = ".";
+
+// -- Strategies --
+
+/// An ecosystem's own cleanup command, pointed at a specific cache folder.
+pub struct ToolCommand {
+    pub program: &'static str,
+    pub args: &'static [&'static str],
+    pub env: &'static [(&'static str, &'static str)],
+}
+
+/// How to shrink a package cache without deleting the whole folder, which
+/// often also holds toolchains, installed binaries or credentials.
+pub struct CleanupStrategy {
+    pub folder_name: &'static str,
+    /// Preferred when installed.
+    pub tool: Option<ToolCommand>,
+    /// Subdirectories that only hold downloaded or derived data. Used when
+    /// the tool is missing or fails.
+    pub safe_subdirs: &'static [&'static str],
+    /// Subdirectories that are mostly cache but may hold data that can't be
+    /// fetched again. Shown in the preview as caution, never trashed.
+    pub caution_subdirs: &'static [&'static str],
+    pub notes: &'static str,
+}
+
+const STRATEGIES: &[CleanupStrategy] = &[
+    CleanupStrategy {
+        folder_name: ".npm",
+        tool: Some(ToolCommand {
+            program: "npm",
+            args: &["cache", "clean", "--force", "--cache", FOLDER_PLACEHOLDER],
+            env: &[],
+        }),
+        safe_subdirs: &["_cacache"],
+        caution_subdirs: &[],
+        notes: "Keeps npm logs and config; packages are downloaded again on demand",
+    },
+    CleanupStrategy {
+        folder_name: ".pnpm-store",
+        tool: Some(ToolCommand {
+            program: "pnpm",
+            args: &["store", "prune", "--store-dir", FOLDER_PLACEHOLDER],
+            env: &[],
+        }),
+        safe_subdirs: &[],
+        caution_subdirs: &[],
+        notes: "Only packages no project references are pruned",
+    },
+    CleanupStrategy {
+        folder_name: ".cargo",
+        tool: Some(ToolCommand {
+            program: "cargo-cache",
+            // Only the downloaded .crate archives; `--autoclean` would also
+            // take the unpacked sources and git checkouts.
+            args: &["cache", "--remove-dir=registry-crate-cache"],
+            env: &[("CARGO_HOME", FOLDER_PLACEHOLDER)],
+        }),
+        safe_subdirs: &["registry/cache"],
+        caution_subdirs: &[],
+        notes: "Installed binaries in bin/ and credentials are never touched",
+    },
+    CleanupStrategy {
+        folder_name: ".gradle",
+        // Gradle prunes its own caches and has no command to force it.
+        tool: None,
+        safe_subdirs: &[
+            "caches/build-cache-1",
+            "caches/transforms-3",
+            "caches/transforms-4",
+        ],
+        caution_subdirs: &[],
+        notes: "Keeps gradle.properties, wrapper distributions and the dependency cache",
+    },
+    CleanupStrategy {
+        folder_name: ".m2",
+        tool: None,
+        safe_subdirs: &[],
+        // Artifacts from `mvn install` live next to downloaded ones and
+        // can't be fetched again.
+        caution_subdirs: &["repository"],
+        notes: "Nothing is removed automatically; the local repository also holds artifacts you installed yourself",
+    },
+    CleanupStrategy {
+        folder_name: ".yarn",
+        tool: None,
+        safe_subdirs: &["berry/cache"],
+        caution_subdirs: &[],
+        notes: "Keeps global installs and yarn's own releases",
+    },
+];
+
+pub fn cleanup_strategy(folder: &Path) -> Option<&'static CleanupStrategy> {
+    let name = folder.file_name()?.to_str()?;
+    STRATEGIES
+        .iter()
+        .find(|strategy| strategy.folder_name == name)
+}
+
+fn substitute(template: &str, folder: &Path) -> String {
+    template.replace(FOLDER_PLACEHOLDER, &folder.to_string_lossy())
+}
+
+// -- Running tools --
+
+/// How long an ecosystem tool may run before it is killed.
+pub const TOOL_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+const TOOL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub struct ToolOutput {
+    pub success: bool,
+    /// Combined stdout and stderr.
+    pub output: String,
+}
+
+/// Finds and runs external programs. Tests substitute a fake.
+pub trait ToolRunner: Send + Sync {
+    fn locate(&self, program: &str) -> Option<PathBuf>;
+    fn run(
+        &self,
+        program: &Path,
+        args: &[String],
+        env: &[(String, String)],
+        cancel: &CancelToken,
+    ) -> Result<ToolOutput, String>;
+}
+
+pub struct SystemTools;
+
+impl ToolRunner for SystemTools {
+    fn locate(&self, program: &str) -> Option<PathBuf> {
+        std::env::split_paths(&std::env::var_os("PATH")?)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+    }
+
+    fn run(
+        &self,
+        program: &Path,
+        args: &[String],
+        env: &[(String, String)],
+        cancel: &CancelToken,
+    ) -> Result<ToolOutput, String> {
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .envs(env.iter().map(|(key, value)| (key, value)));
+        run_until(command, TOOL_TIMEOUT, cancel)
+            .map_err(|run_error| format!("{}: {run_error}", program.display()))
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            // Whatever was read before a failure is still worth showing.
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).to_string()
+    })
+}
+
+/// Runs `command` to completion with its output captured, unless `timeout`
+/// passes or `cancel` fires first, in which case the tool is killed.
+fn run_until(
+    mut command: Command,
+    timeout: Duration,
+    cancel: &CancelToken,
+) -> Result<ToolOutput, String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|spawn_error| spawn_error.to_string())?;
+    // Both pipes are drained while the tool runs so it never blocks on a
+    // full one.
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|wait_error| wait_error.to_string())?
+        {
+            break status;
+        }
+        let stop_reason = if cancel.is_cancelled() {
+            Some("cancelled".to_string())
+        } else if Instant::now() >= deadline {
+            Some(format!("timed out after {timeout:?}"))
+        } else {
+            None
+        };
+        if let Some(stop_reason) = stop_reason {
+            // Killing only fails when the tool has exited in the meantime.
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(stop_reason);
+        }
+        thread::sleep(TOOL_POLL_INTERVAL);
+    };
+
+    let mut combined = stdout.join().unwrap_or_default();
+    combined.push_str(&stderr.join().unwrap_or_default());
+    Ok(ToolOutput {
+        success: status.success(),
+        output: combined,
+    })
+}
+
+// -- Shared types --
+
+/// What a cleanup would do, without doing it.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CleanupPreview {
+    pub path: String,
+    pub size_bytes: u64,
+    /// The command line that would run, if the tool is installed.
+    pub tool_command: Option<String>,
+    pub tool_available: bool,
+    /// Existing safe subdirectories the fallback would trash.
+    pub safe_subdirs: Vec<String>,
+    /// Existing subdirectories worth a look but left for the user to clean.
+    pub caution_subdirs: Vec<String>,
+    pub notes: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ToolRun {
+    pub command: String,
+    pub success: bool,
+    pub output: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CleanupReport {
+    pub path: String,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub freed_bytes: u64,
+    /// Set when the ecosystem tool was run.
+    pub tool_run: Option<ToolRun>,
+    /// Safe subdirectories moved to the trash by the fallback.
+    pub trashed: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// -- Cleanup --
+
+fn tool_command_line(tool: &ToolCommand, folder: &Path) -> (Vec<String>, String) {
+    let args: Vec<String> = tool
+        .args
+        .iter()
+        .map(|arg| substitute(arg, folder))
+        .collect();
+    let command_line = std::iter::once(tool.program.to_string())
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (args, command_line)
+}
+
+fn existing_subdirs(folder: &Path, subdirs: &[&str]) -> Vec<PathBuf> {
+    subdirs
+        .iter()
+        .map(|subdir| match *subdir {
+            WHOLE_FOLDER => folder.to_path_buf(),
+            _ => folder.join(subdir),
+        })
+        .filter(|subdir_path| subdir_path.is_dir())
+        .collect()
+}
+
+fn strategy_for(folder: &Path) -> Result<&'static CleanupStrategy, String> {
+    cleanup_strategy(folder).ok_or_else(|| format!("No cleanup strategy for {}", folder.display()))
+}
+
+pub fn preview_cleanup(
+    folder: &Path,
+    tools: &dyn ToolRunner,
+    cancel: &CancelToken,
+) -> Result<CleanupPreview, String> {
+    Ok(preview_strategy(
+        folder,
+        strategy_for(folder)?,
+        tools,
+        cancel,
+    ))
+}
+
+pub(super) fn preview_strategy(
+    folder: &Path,
+    strategy: &CleanupStrategy,
+    tools: &dyn ToolRunner,
+    cancel: &CancelToken,
+) -> CleanupPreview {
+    CleanupPreview {
+        path: folder.to_string_lossy().to_string(),
+        size_bytes: dir_size(folder, cancel),
+        tool_command: strategy
+            .tool
+            .as_ref()
+            .map(|tool| tool_command_line(tool, folder).1),
+        tool_available: strategy
+            .tool
+            .as_ref()
+            .is_some_and(|tool| tools.locate(tool.program).is_some()),
+        safe_subdirs: existing_subdirs(folder, strategy.safe_subdirs)
+            .iter()
+            .map(|subdir| subdir.to_string_lossy().to_string())
+            .collect(),
+        caution_subdirs: existing_subdirs(folder, strategy.caution_subdirs)
+            .iter()
+            .map(|subdir| subdir.to_string_lossy().to_string())
+            .collect(),
+        notes: strategy.notes.to_string(),
+    }
+}
+
+/// A folder to clean, the strategy to clean it with, and the category its
+/// trashed subdirectories are audited under.
+pub(super) struct CleanupTarget<'a> {
+    pub folder: &'a Path,
+    pub strategy: &'a CleanupStrategy,
+    pub category: Category,
+}
+
+/// Shrinks a package cache: runs the ecosystem's tool when it is installed,
+/// and otherwise, or when it fails, moves the documented safe subdirectories
+/// to the trash. The folder must pass the guard first, and the tool run and
+/// each trashed subdirectory are audited like a purge.
+pub fn clean_cache(
+    folder: &Path,
+    guard: &PathGuard,
+    tools: &dyn ToolRunner,
+    trasher: &dyn Trasher,
+    audit: &AuditLog,
+    cancel: &CancelToken,
+) -> Result<CleanupReport, String> {
+    let target = CleanupTarget {
+        folder,
+        strategy: strategy_for(folder)?,
+        category: Category::PackageCaches,
+    };
+    clean_target(&target, guard, tools, trasher, audit, cancel)
+}
+
+pub(super) fn clean_target(
+    target: &CleanupTarget,
+    guard: &PathGuard,
+    tools: &dyn ToolRunner,
+    trasher: &dyn Trasher,
+    audit: &AuditLog,
+    cancel: &CancelToken,
+) -> Result<CleanupReport, String> {
+    let CleanupTarget {
+        folder,
+        strategy,
+        category,
+    } = target;
+    // Package caches often keep protected credentials next to their safe
+    // subdirectories, so the folder only has to be a place that may be
+    // cleaned; each subdirectory passes the full check before it is trashed.
+    guard.check_in_place(folder)?;
+    if !folder.is_dir() {
+        return Err(format!("{} is not a directory", folder.display()));
+    }
+
+    let mut audit_writer = audit.writer()?;
+    let plan_id = next_plan_id();
+    let mut audit_index = 0;
+    let bytes_before = dir_size(folder, cancel);
+    let mut errors = Vec::new();
+    let mut tool_run = None;
+
+    if let Some(tool) = &strategy.tool {
+        if let Some(program) = tools.locate(tool.program) {
+            let (args, command) = tool_command_line(tool, folder);
+            let env: Vec<(String, String)> = tool
+                .env
+                .iter()
+                .map(|(key, value)| (key.to_string(), substitute(value, folder)))
+                .collect();
+            let run = match tools.run(&program, &args, &env, cancel) {
+                Ok(tool_output) => ToolRun {
+                    command,
+                    success: tool_output.success,
+                    output: tool_output.output,
+                },
+                Err(run_error) => ToolRun {
+                    command,
+                    success: false,
+                    output: run_error,
+                },
+            };
+            let item = PurgeItemResult {
+                path: folder.to_string_lossy().to_string(),
+                size_bytes: bytes_before.saturating_sub(dir_size(folder, cancel)),
+                category: category.clone(),
+                outcome: if run.success {
+                    PurgeOutcome::Cleaned
+                } else {
+                    PurgeOutcome::Failed
+                },
+                error: (!run.success).then(|| format!("{} failed: {}", run.command, run.output)),
+                trash_location: None,
+            };
+            audit_writer.append(&AuditEntry::new(&plan_id, audit_index, unix_now(), &item))?;
+            audit_index += 1;
+            tool_run = Some(run);
+        }
+    }
+
+    let mut trashed = Vec::new();
+    // A cancelled cleanup stops at the tool; nothing more is trashed.
+    if !tool_run.as_ref().is_some_and(|run| run.success) && !cancel.is_cancelled() {
+        for subdir in existing_subdirs(folder, strategy.safe_subdirs) {
+            if let Err(reason) = guard.check(&subdir) {
+                errors.push(reason);
+                continue;
+            }
+            let size_bytes = dir_size(&subdir, cancel);
+            let trash_result = trasher.trash(&subdir);
+            let item = PurgeItemResult {
+                path: subdir.to_string_lossy().to_string(),
+                size_bytes,
+                category: category.clone(),
+                outcome: if trash_result.is_ok() {
+                    PurgeOutcome::Trashed
+                } else {
+                    PurgeOutcome::Failed
+                },
+                error: trash_result.as_ref().err().cloned(),
+                trash_location: trash_result
+                    .ok()
+                    .flatten()
+                    .map(|location| location.to_string_lossy().to_string()),
+            };
+            audit_writer.append(&AuditEntry::new(&plan_id, audit_index, unix_now(), &item))?;
+            audit_index += 1;
+
+            match item.error {
+                Some(trash_error) => errors.push(format!("{}: {trash_error}", item.path)),
+                None => trashed.push(item.path),
+            }
+        }
+    }
+
+    let bytes_after = dir_size(folder, cancel);
+    Ok(CleanupReport {
+        path: folder.to_string_lossy().to_string(),
+        bytes_before,
+        bytes_after,
+        freed_bytes: bytes_before.saturating_sub(bytes_after),
+        tool_run,
+        trashed,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_support::{scratch_audit, write_file, DeleteTrash, FakeTools};
+
+    struct Fixture {
+        home: tempfile::TempDir,
+        _data_dir: tempfile::TempDir,
+        audit: AuditLog,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let home = tempfile::tempdir().unwrap();
+            write_file(
+                &home.path().join(".cargo/registry/cache/index/serde.crate"),
+                700,
+            );
+            write_file(&home.path().join(".cargo/bin/rg"), 50);
+            write_file(&home.path().join(".cargo/credentials.toml"), 5);
+            write_file(&home.path().join(".npm/_cacache/content/blob"), 400);
+            write_file(&home.path().join(".npm/_logs/debug.log"), 10);
+            let (data_dir, audit) = scratch_audit();
+            Self {
+                home,
+                _data_dir: data_dir,
+                audit,
+            }
+        }
+
+        fn home_path(&self) -> PathBuf {
+            self.home.path().canonicalize().unwrap()
+        }
+
+        /// The production guard, with its home protections.
+        fn guard(&self) -> PathGuard {
+            PathGuard::new(Some(&self.home_path()), Some(&self.home_path()), &[])
+        }
+
+        fn clean(&self, folder: &str, tools: &dyn ToolRunner) -> CleanupReport {
+            clean_cache(
+                &self.home_path().join(folder),
+                &self.guard(),
+                tools,
+                &DeleteTrash,
+                &self.audit,
+                &CancelToken::new(),
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn preview_describes_the_tool_and_fallback() {
+        let fixture = Fixture::new();
+        let npm = fixture.home.path().join(".npm");
+        let preview =
+            preview_cleanup(&npm, &FakeTools::new(&["npm"]), &CancelToken::new()).unwrap();
+
+        assert_eq!(preview.size_bytes, 410);
+        assert!(preview.tool_available);
+        assert_eq!(
+            preview.tool_command.unwrap(),
+            format!("npm cache clean --force --cache {}", npm.display())
+        );
+        assert_eq!(preview.safe_subdirs.len(), 1);
+        assert!(fixture.home.path().join(".npm/_cacache").exists());
+    }
+
+    #[test]
+    fn runs_the_tool_when_installed() {
+        let fixture = Fixture::new();
+        let mut tools = FakeTools::new(&["cargo-cache"]);
+        // What `--remove-dir=registry-crate-cache` deletes.
+        tools.removes = vec![fixture.home.path().join(".cargo/registry/cache")];
+
+        let report = fixture.clean(".cargo", &tools);
+        assert_eq!(report.bytes_before, 755);
+        assert_eq!(report.freed_bytes, 700);
+        assert!(report.tool_run.unwrap().success);
+        assert!(report.trashed.is_empty());
+        assert!(fixture.home.path().join(".cargo/bin/rg").exists());
+
+        let calls = tools.calls.lock().unwrap();
+        let cargo_home = fixture.home_path().join(".cargo");
+        assert_eq!(calls[0].0, ["cache", "--remove-dir=registry-crate-cache"]);
+        assert_eq!(
+            calls[0].1,
+            [(
+                "CARGO_HOME".to_string(),
+                cargo_home.to_string_lossy().to_string()
+            )]
+        );
+
+        let logged = fixture.audit.entries().unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].outcome, PurgeOutcome::Cleaned);
+        assert_eq!(logged[0].path, cargo_home.to_string_lossy());
+        assert_eq!(logged[0].size_bytes, 700);
+    }
+
+    #[test]
+    fn refuses_folders_the_guard_rejects() {
+        let fixture = Fixture::new();
+        let outside = tempfile::tempdir().unwrap();
+        write_file(&outside.path().join(".npm/_cacache/blob"), 10);
+        let tools = FakeTools::new(&["npm"]);
+
+        let result = clean_cache(
+            &outside.path().join(".npm"),
+            &fixture.guard(),
+            &tools,
+            &DeleteTrash,
+            &fixture.audit,
+            &CancelToken::new(),
+        );
+        assert!(result.is_err());
+        assert!(tools.calls.lock().unwrap().is_empty());
+        assert!(outside.path().join(".npm/_cacache/blob").exists());
+    }
+
+    #[test]
+    fn leaves_the_maven_repository_to_the_user() {
+        let fixture = Fixture::new();
+        write_file(
+            &fixture.home.path().join(".m2/repository/com/acme/app.jar"),
+            300,
+        );
+        let m2 = fixture.home_path().join(".m2");
+        let preview = preview_cleanup(&m2, &FakeTools::none(), &CancelToken::new()).unwrap();
+        assert!(preview.safe_subdirs.is_empty());
+        assert_eq!(preview.caution_subdirs.len(), 1);
+
+        let report = fixture.clean(".m2", &FakeTools::none());
+        assert!(report.trashed.is_empty());
+        assert_eq!(report.freed_bytes, 0);
+        assert!(m2.join("repository/com/acme/app.jar").exists());
+    }
+
+    #[test]
+    fn falls_back_to_safe_subdirs_without_the_tool() {
+        let fixture = Fixture::new();
+        let report = fixture.clean(".cargo", &FakeTools::new(&[]));
+
+        assert!(report.tool_run.is_none());
+        assert_eq!(report.freed_bytes, 700);
+        assert_eq!(report.trashed.len(), 1);
+        assert!(fixture.home.path().join(".cargo/bin/rg").exists());
+        assert!(fixture.home.path().join(".cargo/credentials.toml").exists());
+
+        let logged = fixture.audit.entries().unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].size_bytes, 700);
+    }
+
+    #[test]
+    fn falls_back_when_the_tool_fails() {
+        let fixture = Fixture::new();
+        let mut tools = FakeTools::new(&["npm"]);
+        tools.succeeds = false;
+
+        let report = fixture.clean(".npm", &tools);
+        assert!(!report.tool_run.unwrap().success);
+        assert_eq!(report.freed_bytes, 400);
+        assert!(fixture.home.path().join(".npm/_logs/debug.log").exists());
+
+        let outcomes: Vec<PurgeOutcome> = fixture
+            .audit
+            .entries()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.outcome)
+            .collect();
+        assert_eq!(outcomes, [PurgeOutcome::Failed, PurgeOutcome::Trashed]);
+    }
+
+    #[test]
+    fn unknown_folders_have_no_strategy() {
+        let fixture = Fixture::new();
+        let result = clean_cache(
+            &fixture.home.path().join("Documents"),
+            &fixture.guard(),
+            &FakeTools::new(&[]),
+            &DeleteTrash,
+            &fixture.audit,
+            &CancelToken::new(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn captures_tool_output() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo out; echo err >&2; exit 3"]);
+
+        let tool_output = run_until(command, TOOL_TIMEOUT, &CancelToken::new()).unwrap();
+        assert!(!tool_output.success);
+        assert_eq!(tool_output.output, "out\nerr\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tools_are_killed_at_the_deadline_or_on_cancel() {
+        let sleeper = || {
+            let mut command = Command::new("sleep");
+            command.arg("30");
+            command
+        };
+        let started = Instant::now();
+
+        let timed_out =
+            run_until(sleeper(), Duration::from_millis(200), &CancelToken::new()).unwrap_err();
+        assert!(timed_out.contains("timed out"), "{timed_out}");
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        assert_eq!(
+            run_until(sleeper(), TOOL_TIMEOUT, &cancel).unwrap_err(),
+            "cancelled"
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
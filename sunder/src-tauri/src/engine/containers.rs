@@ -0,0 +1,897 @@
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::audit::{AuditEntry, AuditLog};
+use super::category::Category;
+use super::purge::{next_plan_id, unix_now, PurgeItemResult, PurgeOutcome};
+
+/// `docker system df` computes volume sizes and prunes can take a while on
+/// large stores, so requests get a generous timeout.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Docker 23 and later label the volumes it creates for `VOLUME` lines and
+/// `-v /path` mounts; older engines and Podman only give them a random id.
+const ANONYMOUS_VOLUME_LABEL: &str = "com.docker.volume.anonymous";
+
+// -- Shared types --
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineResource {
+    Image,
+    Container,
+    Volume,
+    BuildCache,
+}
+
+/// Something the engine could remove without affecting anything running:
+/// a dangling image, a stopped container, an unused volume or an unused
+/// build cache record.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ReclaimableItem {
+    pub resource: EngineResource,
+    pub id: String,
+    pub name: String,
+    pub size_bytes: u64,
+    /// An unused volume someone gave a name, which usually means it holds
+    /// data a project expects to find again. Only pruned on request.
+    pub named_volume: bool,
+}
+
+/// One row of `docker system df`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct ResourceUsage {
+    pub total_count: u64,
+    pub total_bytes: u64,
+    pub reclaimable_count: u64,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EngineDiskUsage {
+    pub socket: String,
+    pub images: ResourceUsage,
+    pub containers: ResourceUsage,
+    pub volumes: ResourceUsage,
+    pub build_cache: ResourceUsage,
+    /// Largest first. Named volumes are listed here but not counted as
+    /// reclaimable in `volumes`.
+    pub reclaimable: Vec<ReclaimableItem>,
+}
+
+/// The reclaimable items a user reviewed and chose to remove.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct PruneSelection {
+    /// Ids from [`EngineDiskUsage::reclaimable`].
+    pub ids: Vec<String>,
+    /// Named volumes in `ids` are skipped unless this is set.
+    pub include_named_volumes: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PruneItemResult {
+    pub resource: EngineResource,
+    pub id: String,
+    pub name: String,
+    pub size_bytes: u64,
+    pub removed: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PruneReport {
+    pub socket: String,
+    pub items: Vec<PruneItemResult>,
+    pub reclaimed_bytes: u64,
+    /// Selected ids that are no longer reclaimable, left alone.
+    pub skipped: Vec<String>,
+}
+
+// -- `/system/df` response --
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct SystemDf {
+    images: Option<Vec<DfImage>>,
+    containers: Option<Vec<DfContainer>>,
+    volumes: Option<Vec<DfVolume>>,
+    build_cache: Option<Vec<DfBuildCache>>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct DfImage {
+    id: String,
+    repo_tags: Option<Vec<String>>,
+    size: i64,
+    /// `-1` when the engine did not compute it.
+    shared_size: i64,
+    containers: i64,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct DfContainer {
+    id: String,
+    names: Option<Vec<String>>,
+    state: String,
+    size_rw: Option<i64>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct DfVolume {
+    name: String,
+    labels: Option<HashMap<String, String>>,
+    usage_data: Option<DfVolumeUsage>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct DfVolumeUsage {
+    size: i64,
+    ref_count: i64,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct DfBuildCache {
+    #[serde(rename = "ID")]
+    id: String,
+    description: String,
+    size: i64,
+    in_use: bool,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct BuildPruneResponse {
+    caches_deleted: Option<Vec<String>>,
+}
+
+/// Engines report unknown sizes as `-1`.
+fn known_size(size: i64) -> u64 {
+    size.max(0) as u64
+}
+
+fn is_dangling(image: &DfImage) -> bool {
+    image
+        .repo_tags
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .all(|tag| tag == "<none>:<none>")
+}
+
+fn is_anonymous(volume: &DfVolume) -> bool {
+    let labelled = volume
+        .labels
+        .as_ref()
+        .is_some_and(|labels| labels.contains_key(ANONYMOUS_VOLUME_LABEL));
+    let random_name =
+        volume.name.len() == 64 && volume.name.bytes().all(|byte| byte.is_ascii_hexdigit());
+    labelled || random_name
+}
+
+impl SystemDf {
+    fn into_usage(self, socket: &Path) -> EngineDiskUsage {
+        let mut usage = EngineDiskUsage {
+            socket: socket.to_string_lossy().to_string(),
+            images: ResourceUsage::default(),
+            containers: ResourceUsage::default(),
+            volumes: ResourceUsage::default(),
+            build_cache: ResourceUsage::default(),
+            reclaimable: Vec::new(),
+        };
+
+        for image in self.images.unwrap_or_default() {
+            // Only the layers no other image shares are freed.
+            let unique_bytes = known_size(image.size - image.shared_size.max(0));
+            let reclaimable = is_dangling(&image) && image.containers <= 0;
+            record(
+                &mut usage.images,
+                known_size(image.size),
+                reclaimable,
+                unique_bytes,
+            );
+            if reclaimable {
+                usage.reclaimable.push(ReclaimableItem {
+                    resource: EngineResource::Image,
+                    name: short_id(&image.id),
+                    id: image.id,
+                    size_bytes: unique_bytes,
+                    named_volume: false,
+                });
+            }
+        }
+
+        for container in self.containers.unwrap_or_default() {
+            let size_bytes = known_size(container.size_rw.unwrap_or(0));
+            let reclaimable = matches!(container.state.as_str(), "exited" | "created" | "dead");
+            record(&mut usage.containers, size_bytes, reclaimable, size_bytes);
+            if reclaimable {
+                let name = container
+                    .names
+                    .as_deref()
+                    .and_then(|names| names.first())
+                    .map(|name| name.trim_start_matches('/').to_string())
+                    .unwrap_or_else(|| short_id(&container.id));
+                usage.reclaimable.push(ReclaimableItem {
+                    resource: EngineResource::Container,
+                    id: container.id,
+                    name,
+                    size_bytes,
+                    named_volume: false,
+                });
+            }
+        }
+
+        for volume in self.volumes.unwrap_or_default() {
+            let anonymous = is_anonymous(&volume);
+            let volume_usage = volume.usage_data.unwrap_or_default();
+            let size_bytes = known_size(volume_usage.size);
+            let unused = volume_usage.ref_count == 0;
+            record(
+                &mut usage.volumes,
+                size_bytes,
+                unused && anonymous,
+                size_bytes,
+            );
+            if unused {
+                usage.reclaimable.push(ReclaimableItem {
+                    resource: EngineResource::Volume,
+                    name: if anonymous {
+                        short_id(&volume.name)
+                    } else {
+                        volume.name.clone()
+                    },
+                    id: volume.name,
+                    size_bytes,
+                    named_volume: !anonymous,
+                });
+            }
+        }
+
+        for cache in self.build_cache.unwrap_or_default() {
+            let size_bytes = known_size(cache.size);
+            record(
+                &mut usage.build_cache,
+                size_bytes,
+                !cache.in_use,
+                size_bytes,
+            );
+            if !cache.in_use {
+                usage.reclaimable.push(ReclaimableItem {
+                    resource: EngineResource::BuildCache,
+                    name: if cache.description.is_empty() {
+                        short_id(&cache.id)
+                    } else {
+                        cache.description
+                    },
+                    id: cache.id,
+                    size_bytes,
+                    named_volume: false,
+                });
+            }
+        }
+
+        usage
+            .reclaimable
+            .sort_by_key(|item| std::cmp::Reverse(item.size_bytes));
+        usage
+    }
+}
+
+fn record(row: &mut ResourceUsage, size_bytes: u64, reclaimable: bool, reclaimable_bytes: u64) {
+    row.total_count += 1;
+    row.total_bytes += size_bytes;
+    if reclaimable {
+        row.reclaimable_count += 1;
+        row.reclaimable_bytes += reclaimable_bytes;
+    }
+}
+
+/// Percent-encodes a query parameter value.
+fn query_escape(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// The 12-character form `docker` prints, without any `sha256:` prefix.
+fn short_id(id: &str) -> String {
+    let hex = id.rsplit(':').next().unwrap_or(id);
+    hex.chars().take(12).collect()
+}
+
+// -- HTTP over the Unix socket --
+
+/// Splits a raw HTTP/1.1 response into its status code and body, undoing
+/// chunked transfer encoding.
+fn parse_response(raw: &[u8]) -> Result<(u16, Vec<u8>), String> {
+    let header_end = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or("Truncated response from the container engine")?;
+    let head = String::from_utf8_lossy(&raw[..header_end]);
+    let body = &raw[header_end + 4..];
+
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .and_then(|status_line| status_line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or("Malformed status line from the container engine")?;
+
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+    }
+
+    let body = if chunked {
+        decode_chunked(body)?
+    } else {
+        match content_length {
+            Some(length) => body.get(..length).unwrap_or(body).to_vec(),
+            None => body.to_vec(),
+        }
+    };
+    Ok((status, body))
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, String> {
+    let truncated = || "Truncated chunked response from the container engine".to_string();
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(truncated)?;
+        let size_line = String::from_utf8_lossy(&body[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| format!("Bad chunk size {size_hex:?} from the container engine"))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size).ok_or_else(truncated)?);
+        body = body.get(size + 2..).ok_or_else(truncated)?;
+    }
+}
+
+#[cfg(unix)]
+fn exchange(socket: &Path, request: &str) -> Result<Vec<u8>, String> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let connect_error = |io_error: std::io::Error| format!("{}: {io_error}", socket.display());
+    let mut stream = UnixStream::connect(socket).map_err(connect_error)?;
+    stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT))
+        .map_err(connect_error)?;
+    stream
+        .write_all(request.as_bytes())
+        .map_err(connect_error)?;
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw).map_err(connect_error)?;
+    Ok(raw)
+}
+
+#[cfg(not(unix))]
+fn exchange(_socket: &Path, _request: &str) -> Result<Vec<u8>, String> {
+    Err("Container engines are only reachable over Unix sockets".into())
+}
+
+/// The error message engines put in failed responses, or the raw body.
+fn error_message(status: u16, body: &[u8]) -> String {
+    #[derive(serde::Deserialize)]
+    struct EngineError {
+        message: String,
+    }
+    let message = serde_json::from_slice::<EngineError>(body)
+        .map(|engine_error| engine_error.message)
+        .unwrap_or_else(|_| String::from_utf8_lossy(body).trim().to_string());
+    format!("Engine returned {status}: {message}")
+}
+
+// -- Client --
+
+/// Sockets where a Docker-compatible API usually listens, in the order they
+/// are tried: the ones the environment points at, the system daemon, rootless
+/// Podman, then Docker Desktop and Colima in the home directory.
+fn socket_candidates(
+    docker_host: Option<&str>,
+    container_host: Option<&str>,
+    runtime_dir: Option<&Path>,
+    home: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = [docker_host, container_host]
+        .into_iter()
+        .flatten()
+        .filter_map(|host| host.strip_prefix("unix://"))
+        .map(PathBuf::from)
+        .collect();
+    candidates.push(PathBuf::from("/var/run/docker.sock"));
+    if let Some(runtime_dir) = runtime_dir {
+        candidates.push(runtime_dir.join("podman/podman.sock"));
+        candidates.push(runtime_dir.join("docker.sock"));
+    }
+    if let Some(home) = home {
+        candidates.push(home.join(".docker/run/docker.sock"));
+        candidates.push(home.join(".colima/default/docker.sock"));
+    }
+    candidates
+}
+
+/// Talks to a Docker or Podman engine through its Docker-compatible API.
+pub struct EngineClient {
+    socket: PathBuf,
+}
+
+impl EngineClient {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    /// The first engine socket that exists.
+    pub fn detect() -> Option<Self> {
+        let docker_host = std::env::var("DOCKER_HOST").ok();
+        let container_host = std::env::var("CONTAINER_HOST").ok();
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+        socket_candidates(
+            docker_host.as_deref(),
+            container_host.as_deref(),
+            runtime_dir.as_deref(),
+            dirs::home_dir().as_deref(),
+        )
+        .into_iter()
+        .find(|candidate| candidate.exists())
+        .map(Self::new)
+    }
+
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    fn request(&self, method: &str, path: &str) -> Result<Vec<u8>, String> {
+        let request = format!(
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+        let (status, body) = parse_response(&exchange(&self.socket, &request)?)?;
+        if (200..300).contains(&status) {
+            Ok(body)
+        } else {
+            Err(error_message(status, &body))
+        }
+    }
+
+    fn request_json<T: DeserializeOwned>(&self, method: &str, path: &str) -> Result<T, String> {
+        let body = self.request(method, path)?;
+        serde_json::from_slice(&body)
+            .map_err(|parse_error| format!("Unexpected response to {path}: {parse_error}"))
+    }
+
+    /// What the engine stores and how much of it could be pruned.
+    pub fn disk_usage(&self) -> Result<EngineDiskUsage, String> {
+        let system_df: SystemDf = self.request_json("GET", "/system/df")?;
+        Ok(system_df.into_usage(&self.socket))
+    }
+
+    /// Removes the selected items, checked against a fresh
+    /// [`EngineClient::disk_usage`] so only what is still reclaimable goes.
+    /// Images, containers and volumes are removed one at a time without
+    /// forcing, so anything that came into use since is refused by the engine
+    /// rather than removed. Every removal is written to `audit`.
+    pub fn prune(
+        &self,
+        selection: &PruneSelection,
+        audit: &AuditLog,
+    ) -> Result<PruneReport, String> {
+        let usage = self.disk_usage()?;
+        let selected: Vec<&ReclaimableItem> = usage
+            .reclaimable
+            .iter()
+            .filter(|item| selection.ids.contains(&item.id))
+            .collect();
+        let skipped = selection
+            .ids
+            .iter()
+            .filter(|id| !selected.iter().any(|item| &item.id == *id))
+            .cloned()
+            .collect();
+
+        let mut audit_writer = audit.writer()?;
+        let plan_id = next_plan_id();
+        let mut items: Vec<PruneItemResult> = Vec::with_capacity(selected.len());
+        let mut record_result =
+            |item: &ReclaimableItem, path: &str, error: Option<String>| -> Result<(), String> {
+                let audited = PurgeItemResult {
+                    path: format!("{}:{path}", usage.socket),
+                    size_bytes: item.size_bytes,
+                    category: Category::VirtualMachines,
+                    outcome: if error.is_none() {
+                        PurgeOutcome::Removed
+                    } else {
+                        PurgeOutcome::Failed
+                    },
+                    error: error.clone(),
+                    trash_location: None,
+                };
+                audit_writer.append(&AuditEntry::new(
+                    &plan_id,
+                    items.len(),
+                    unix_now(),
+                    &audited,
+                ))?;
+                items.push(PruneItemResult {
+                    resource: item.resource,
+                    id: item.id.clone(),
+                    name: item.name.clone(),
+                    size_bytes: item.size_bytes,
+                    removed: error.is_none(),
+                    error,
+                });
+                Ok(())
+            };
+
+        let mut refused = Vec::new();
+        for item in selected
+            .iter()
+            .filter(|item| item.resource != EngineResource::BuildCache)
+        {
+            if item.named_volume && !selection.include_named_volumes {
+                refused.push(PruneItemResult {
+                    resource: item.resource,
+                    id: item.id.clone(),
+                    name: item.name.clone(),
+                    size_bytes: item.size_bytes,
+                    removed: false,
+                    error: Some("Named volume; include named volumes to prune it".into()),
+                });
+                continue;
+            }
+            let path = api_path(item);
+            record_result(item, &path, self.request("DELETE", &path).err())?;
+        }
+
+        let build_caches: Vec<&&ReclaimableItem> = selected
+            .iter()
+            .filter(|item| item.resource == EngineResource::BuildCache)
+            .collect();
+        if !build_caches.is_empty() {
+            let ids: Vec<&str> = build_caches.iter().map(|item| item.id.as_str()).collect();
+            let path = build_prune_path(&ids);
+            match self.request_json::<BuildPruneResponse>("POST", &path) {
+                Ok(response) => {
+                    let deleted: HashSet<String> = response
+                        .caches_deleted
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect();
+                    for item in &build_caches {
+                        let kept =
+                            (!deleted.contains(&item.id)).then(|| "Kept by the engine".to_string());
+                        record_result(item, &path, kept)?;
+                    }
+                }
+                Err(prune_error) => {
+                    for item in &build_caches {
+                        record_result(item, &path, Some(prune_error.clone()))?;
+                    }
+                }
+            }
+        }
+        items.extend(refused);
+
+        Ok(PruneReport {
+            socket: usage.socket.clone(),
+            reclaimed_bytes: items
+                .iter()
+                .filter(|item| item.removed)
+                .map(|item| item.size_bytes)
+                .sum(),
+            items,
+            skipped,
+        })
+    }
+}
+
+/// Where the engine API deletes an item; build cache has no such path and
+/// is pruned by id instead.
+fn api_path(item: &ReclaimableItem) -> String {
+    match item.resource {
+        EngineResource::Image => format!("/images/{}", item.id),
+        EngineResource::Container => format!("/containers/{}", item.id),
+        EngineResource::Volume => format!("/volumes/{}", item.id),
+        EngineResource::BuildCache => build_prune_path(&[item.id.as_str()]),
+    }
+}
+
+/// Prunes exactly the given build cache records through the documented
+/// `id` filter of `POST /build/prune`.
+fn build_prune_path(ids: &[&str]) -> String {
+    let filters = serde_json::json!({ "id": ids }).to_string();
+    format!("/build/prune?filters={}", query_escape(&filters))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::engine::test_support::scratch_audit;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+    use std::sync::{Arc, Mutex};
+
+    const SYSTEM_DF: &str = r#"{
+        "LayersSize": 9000,
+        "Images": [
+            {"Id": "sha256:aaaaaaaaaaaaaaaa", "RepoTags": ["app:latest"], "Size": 5000, "SharedSize": 1000, "Containers": 1},
+            {"Id": "sha256:bbbbbbbbbbbbbbbb", "RepoTags": ["<none>:<none>"], "Size": 3000, "SharedSize": 1000, "Containers": 0},
+            {"Id": "sha256:cccccccccccccccc", "RepoTags": null, "Size": 400, "SharedSize": -1, "Containers": 0}
+        ],
+        "Containers": [
+            {"Id": "1111111111111111", "Names": ["/web"], "State": "running", "SizeRw": 70},
+            {"Id": "2222222222222222", "Names": ["/old-job"], "State": "exited", "SizeRw": 250}
+        ],
+        "Volumes": [
+            {"Name": "db-data", "UsageData": {"Size": 800, "RefCount": 1}},
+            {"Name": "scratch", "UsageData": {"Size": 600, "RefCount": 0}},
+            {"Name": "ci-tmp", "Labels": {"com.docker.volume.anonymous": ""}, "UsageData": {"Size": 500, "RefCount": 0}},
+            {"Name": "abababababababababababababababababababababababababababababababab", "Labels": null, "UsageData": {"Size": 300, "RefCount": 0}}
+        ],
+        "BuildCache": [
+            {"ID": "cache-live", "Description": "mount / from exec", "Size": 90, "InUse": true},
+            {"ID": "cache-old", "Description": "", "Size": 1200, "InUse": false}
+        ]
+    }"#;
+
+    /// A fake engine answering `"METHOD /path"` routes over a Unix socket,
+    /// recording every request line it receives.
+    struct MockEngine {
+        _dir: tempfile::TempDir,
+        socket: PathBuf,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    fn mock_engine(routes: &[(&'static str, u16, &'static str)]) -> MockEngine {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("engine.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let routes = routes.to_vec();
+
+        let seen = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut header_line = String::new();
+                while reader.read_line(&mut header_line).unwrap() > 2 {
+                    header_line.clear();
+                }
+
+                let route: Vec<&str> = request_line.split_whitespace().take(2).collect();
+                let route = route.join(" ");
+                seen.lock().unwrap().push(route.clone());
+                let (status, body) = routes
+                    .iter()
+                    .find(|(path, _, _)| *path == route)
+                    .map(|(_, status, body)| (*status, *body))
+                    .unwrap_or((404, r#"{"message": "page not found"}"#));
+
+                // Successes are chunked and errors are not, to cover both.
+                let response = if status < 300 {
+                    format!(
+                        "HTTP/1.1 {status} OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{body}\r\n0\r\n\r\n",
+                        body.len()
+                    )
+                } else {
+                    format!(
+                        "HTTP/1.1 {status} Error\r\nContent-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        MockEngine {
+            _dir: dir,
+            socket,
+            requests,
+        }
+    }
+
+    #[test]
+    fn disk_usage_lists_what_could_be_pruned() {
+        let engine = mock_engine(&[("GET /system/df", 200, SYSTEM_DF)]);
+        let usage = EngineClient::new(engine.socket.clone())
+            .disk_usage()
+            .unwrap();
+
+        let reclaimable: Vec<(EngineResource, &str, u64)> = usage
+            .reclaimable
+            .iter()
+            .map(|item| (item.resource, item.name.as_str(), item.size_bytes))
+            .collect();
+        assert_eq!(
+            reclaimable,
+            [
+                (EngineResource::Image, "bbbbbbbbbbbb", 2000),
+                (EngineResource::BuildCache, "cache-old", 1200),
+                (EngineResource::Volume, "scratch", 600),
+                (EngineResource::Volume, "ci-tmp", 500),
+                (EngineResource::Image, "cccccccccccc", 400),
+                (EngineResource::Volume, "abababababab", 300),
+                (EngineResource::Container, "old-job", 250),
+            ]
+        );
+        let named: Vec<&str> = usage
+            .reclaimable
+            .iter()
+            .filter(|item| item.named_volume)
+            .map(|item| item.name.as_str())
+            .collect();
+        assert_eq!(named, ["scratch"]);
+        assert_eq!(
+            usage.images,
+            ResourceUsage {
+                total_count: 3,
+                total_bytes: 8400,
+                reclaimable_count: 2,
+                reclaimable_bytes: 2400,
+            }
+        );
+        assert_eq!(usage.containers.reclaimable_bytes, 250);
+        assert_eq!(usage.volumes.reclaimable_count, 2);
+        assert_eq!(usage.volumes.reclaimable_bytes, 800);
+        assert_eq!(usage.build_cache.total_bytes, 1290);
+    }
+
+    fn selection(ids: &[&str], include_named_volumes: bool) -> PruneSelection {
+        PruneSelection {
+            ids: ids.iter().map(|id| id.to_string()).collect(),
+            include_named_volumes,
+        }
+    }
+
+    #[test]
+    fn prune_removes_only_the_selected_items_and_reports_refusals() {
+        let engine = mock_engine(&[
+            ("GET /system/df", 200, SYSTEM_DF),
+            ("DELETE /containers/2222222222222222", 204, ""),
+            (
+                "DELETE /volumes/ci-tmp",
+                409,
+                r#"{"message": "volume is in use"}"#,
+            ),
+            (
+                "POST /build/prune?filters=%7B%22id%22%3A%5B%22cache-old%22%5D%7D",
+                200,
+                r#"{"CachesDeleted": ["cache-old"], "SpaceReclaimed": 1200}"#,
+            ),
+        ]);
+        let (_data_dir, audit) = scratch_audit();
+        let report = EngineClient::new(engine.socket.clone())
+            .prune(
+                &selection(
+                    &["2222222222222222", "ci-tmp", "scratch", "cache-old", "gone"],
+                    false,
+                ),
+                &audit,
+            )
+            .unwrap();
+
+        let outcomes: Vec<(&str, bool)> = report
+            .items
+            .iter()
+            .map(|item| (item.name.as_str(), item.removed))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("ci-tmp", false),
+                ("old-job", true),
+                ("cache-old", true),
+                ("scratch", false),
+            ]
+        );
+        assert_eq!(
+            report.items[0].error.as_deref(),
+            Some("Engine returned 409: volume is in use")
+        );
+        assert_eq!(report.reclaimed_bytes, 1450);
+        assert_eq!(report.skipped, ["gone"]);
+
+        let requests = engine.requests.lock().unwrap();
+        assert!(!requests
+            .iter()
+            .any(|request| request.starts_with("DELETE /images")
+                || request == "DELETE /volumes/scratch"));
+
+        let entries = audit.entries().unwrap();
+        let sent_prune = requests
+            .iter()
+            .find_map(|request| request.strip_prefix("POST "))
+            .unwrap();
+        assert_eq!(
+            entries[2].path,
+            format!("{}:{sent_prune}", engine.socket.display())
+        );
+        let logged: Vec<PurgeOutcome> = entries.iter().map(|entry| entry.outcome).collect();
+        assert_eq!(
+            logged,
+            [
+                PurgeOutcome::Failed,
+                PurgeOutcome::Removed,
+                PurgeOutcome::Removed,
+            ]
+        );
+    }
+
+    #[test]
+    fn named_volumes_are_pruned_only_on_request() {
+        let engine = mock_engine(&[
+            ("GET /system/df", 200, SYSTEM_DF),
+            ("DELETE /volumes/scratch", 204, ""),
+        ]);
+        let (_data_dir, audit) = scratch_audit();
+        let report = EngineClient::new(engine.socket.clone())
+            .prune(&selection(&["scratch"], true), &audit)
+            .unwrap();
+
+        assert!(report.items[0].removed);
+        assert_eq!(report.reclaimed_bytes, 600);
+        let logged = audit.entries().unwrap();
+        assert_eq!(logged.len(), 1);
+        assert!(logged[0].path.ends_with(":/volumes/scratch"));
+    }
+
+    #[test]
+    fn engine_errors_are_surfaced() {
+        let engine = mock_engine(&[(
+            "GET /system/df",
+            500,
+            r#"{"message": "storage driver failed"}"#,
+        )]);
+        let usage_error = EngineClient::new(engine.socket.clone())
+            .disk_usage()
+            .unwrap_err();
+        assert_eq!(usage_error, "Engine returned 500: storage driver failed");
+    }
+
+    #[test]
+    fn environment_sockets_are_tried_first() {
+        let candidates = socket_candidates(
+            Some("unix:///tmp/docker.sock"),
+            Some("tcp://10.0.0.1:2375"),
+            Some(Path::new("/run/user/1000")),
+            Some(Path::new("/home/me")),
+        );
+        assert_eq!(candidates[0], PathBuf::from("/tmp/docker.sock"));
+        assert_eq!(candidates[1], PathBuf::from("/var/run/docker.sock"));
+        assert!(candidates.contains(&PathBuf::from("/run/user/1000/podman/podman.sock")));
+        assert!(candidates.contains(&PathBuf::from("/home/me/.colima/default/docker.sock")));
+    }
+}
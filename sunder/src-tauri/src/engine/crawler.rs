@@ -0,0 +1,652 @@
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::fs::{DirEntry, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use super::cancel::CancelToken;
+use super::errors::{ErrorLog, ScanError};
+use super::mounts::{MountTable, SkippedMount};
+
+/// Which of the two sizes a walk tracks drives totals and sorting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeMode {
+    /// Sum of file lengths, as `ls -l` reports them.
+    #[default]
+    Apparent,
+    /// Blocks actually allocated on disk, as `du` reports them. Sparse files
+    /// count for less and small files count for a whole block.
+    Allocated,
+}
+
+/// What a walk learned about one directory. Children are only kept when the
+/// caller asks for them, so plain size queries stay flat in memory.
+#[derive(Clone, Debug, Default)]
+pub struct DirNode {
+    pub name: String,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    /// Apparent bytes of hard-linked files charged to this directory. Their
+    /// data stays on disk until every other link is gone too, so deleting
+    /// this directory may free less than its size suggests. Which link is
+    /// charged depends on which worker reaches the inode first, so these
+    /// bytes can move between folders from one scan to the next; snapshots
+    /// leave them out of per-folder sizes for that reason.
+    pub shared_bytes: u64,
+    /// Allocated bytes of the same hard-linked files.
+    pub shared_allocated_bytes: u64,
+    pub file_count: u64,
+    /// Subdirectories, largest apparent size first. Empty unless the walk
+    /// retained them.
+    pub children: Vec<DirNode>,
+}
+
+impl DirNode {
+    pub fn size_bytes(&self, mode: SizeMode) -> u64 {
+        match mode {
+            SizeMode::Apparent => self.apparent_bytes,
+            SizeMode::Allocated => self.allocated_bytes,
+        }
+    }
+
+    /// Folds a finished subdirectory's totals into this one.
+    pub fn add_totals(&mut self, child: &DirNode) {
+        self.apparent_bytes += child.apparent_bytes;
+        self.allocated_bytes += child.allocated_bytes;
+        self.shared_bytes += child.shared_bytes;
+        self.shared_allocated_bytes += child.shared_allocated_bytes;
+        self.file_count += child.file_count;
+    }
+}
+
+#[cfg(unix)]
+pub fn allocated_len(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    // `st_blocks` is always counted in 512-byte units, whatever the
+    // filesystem block size is.
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+pub fn allocated_len(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+#[cfg(unix)]
+pub(super) fn hard_link_key(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub(super) fn hard_link_key(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(unix)]
+pub(super) fn device_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+pub(super) fn device_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// A regular file kept by a largest-files pass. Orders by size first.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TrackedFile {
+    pub apparent_bytes: u64,
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    link_key: Option<(u64, u64)>,
+}
+
+/// The largest files seen so far, as a min-heap so the smallest of them is
+/// the one evicted. Memory stays at `limit` entries however big the tree is.
+struct LargestFiles {
+    limit: usize,
+    heap: Mutex<BinaryHeap<Reverse<TrackedFile>>>,
+    /// The smallest size in a full heap. Smaller files are turned away
+    /// without taking the lock.
+    threshold: AtomicU64,
+}
+
+/// State shared by every worker taking part in one scan. A single context
+/// must span the whole scan so a hard-linked inode is charged only once,
+/// no matter how many of its links the walk runs into.
+pub struct WalkContext<'a> {
+    cancel: &'a CancelToken,
+    /// Smallest apparent size of a subdirectory node worth keeping; `None`
+    /// keeps only the totals.
+    retain_children: Option<u64>,
+    /// (device, inode) pairs of multiply-linked files already charged.
+    seen_inodes: Mutex<HashSet<(u64, u64)>>,
+    /// When set, directories on any other device are not entered.
+    root_device: Option<u64>,
+    skipped_mounts: Mutex<Vec<SkippedMount>>,
+    mount_table: OnceLock<MountTable>,
+    errors: ErrorLog,
+    largest_files: Option<LargestFiles>,
+}
+
+impl<'a> WalkContext<'a> {
+    pub fn new(cancel: &'a CancelToken) -> Self {
+        Self {
+            cancel,
+            retain_children: None,
+            seen_inodes: Mutex::new(HashSet::new()),
+            root_device: None,
+            skipped_mounts: Mutex::new(Vec::new()),
+            mount_table: OnceLock::new(),
+            errors: ErrorLog::default(),
+            largest_files: None,
+        }
+    }
+
+    /// Keeps the walk on the filesystem that holds `root`. Directories on
+    /// other devices (network shares, FUSE and bind mounts) are skipped and
+    /// listed in [`WalkContext::skipped_mounts`] instead.
+    pub fn same_filesystem_as(mut self, root: &Path) -> Self {
+        self.root_device = std::fs::metadata(root)
+            .ok()
+            .and_then(|metadata| device_id(&metadata));
+        self
+    }
+
+    /// Keeps every subdirectory node instead of only the totals.
+    pub fn retaining_children(self) -> Self {
+        self.retaining_children_above(0)
+    }
+
+    /// Keeps the subdirectory nodes of at least `min_bytes` apparent size,
+    /// so memory only goes to folders big enough to matter.
+    pub fn retaining_children_above(mut self, min_bytes: u64) -> Self {
+        self.retain_children = Some(min_bytes);
+        self
+    }
+
+    /// Keeps the `limit` largest regular files the walk passes, readable
+    /// through [`WalkContext::largest_files`].
+    pub fn tracking_largest_files(mut self, limit: usize) -> Self {
+        self.largest_files = Some(LargestFiles {
+            limit,
+            heap: Mutex::new(BinaryHeap::with_capacity(limit + 1)),
+            threshold: AtomicU64::new(0),
+        });
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Returns true, and records the mount, when `path` lives on a different
+    /// device than the scan root.
+    pub fn crosses_mount(&self, path: &Path) -> bool {
+        let Some(root_device) = self.root_device else {
+            return false;
+        };
+        let Some(path_device) = std::fs::metadata(path)
+            .ok()
+            .and_then(|metadata| device_id(&metadata))
+        else {
+            return false;
+        };
+        if path_device == root_device {
+            return false;
+        }
+
+        let fs_type = self.mount_table.get_or_init(MountTable::load).fs_type(path);
+        if let Ok(mut skipped_mounts) = self.skipped_mounts.lock() {
+            skipped_mounts.push(SkippedMount {
+                path: path.to_string_lossy().to_string(),
+                fs_type,
+            });
+        }
+        true
+    }
+
+    /// Mounts skipped so far, sorted by path.
+    pub fn skipped_mounts(&self) -> Vec<SkippedMount> {
+        let mut skipped_mounts = self
+            .skipped_mounts
+            .lock()
+            .map(|skipped_mounts| skipped_mounts.clone())
+            .unwrap_or_default();
+        skipped_mounts.sort_by(|mount_a, mount_b| mount_a.path.cmp(&mount_b.path));
+        skipped_mounts
+    }
+
+    /// Records an entry below `dir` that could not be read, so results can
+    /// say which totals are lower bounds.
+    pub fn record_error(&self, dir: &Path, error: &std::io::Error) {
+        self.errors.record(dir, error);
+    }
+
+    pub fn scan_errors(&self) -> Vec<ScanError> {
+        self.errors.errors()
+    }
+
+    pub fn omitted_error_count(&self) -> u64 {
+        self.errors.omitted()
+    }
+
+    /// Charges one regular file to `node`. Links to an inode that has
+    /// already been charged somewhere in this scan add nothing but the count.
+    /// Which link gets charged depends on which worker reaches it first, so
+    /// totals are stable but the folder an inode lands in is not; see
+    /// [`DirNode::shared_bytes`].
+    pub fn add_file(&self, node: &mut DirNode, metadata: &Metadata) {
+        node.file_count += 1;
+
+        if let Some(inode_key) = hard_link_key(metadata) {
+            let first_sighting = self
+                .seen_inodes
+                .lock()
+                .map(|mut seen_inodes| seen_inodes.insert(inode_key))
+                .unwrap_or(true);
+            if !first_sighting {
+                return;
+            }
+            node.shared_bytes += metadata.len();
+            node.shared_allocated_bytes += allocated_len(metadata);
+        }
+
+        node.apparent_bytes += metadata.len();
+        node.allocated_bytes += allocated_len(metadata);
+    }
+
+    /// Offers a regular file to the largest-files pass, if one is running.
+    /// Hard links to a file already kept are not kept again.
+    pub fn offer_file(&self, dir_entry: &DirEntry, metadata: &Metadata) {
+        let Some(largest) = &self.largest_files else {
+            return;
+        };
+        let apparent_bytes = metadata.len();
+        if largest.limit == 0 || apparent_bytes < largest.threshold.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(mut heap) = largest.heap.lock() else {
+            return;
+        };
+
+        let link_key = hard_link_key(metadata);
+        if link_key.is_some() && heap.iter().any(|Reverse(kept)| kept.link_key == link_key) {
+            return;
+        }
+        heap.push(Reverse(TrackedFile {
+            apparent_bytes,
+            path: dir_entry.path(),
+            modified: metadata.modified().ok(),
+            link_key,
+        }));
+        if heap.len() > largest.limit {
+            heap.pop();
+        }
+        if heap.len() == largest.limit {
+            if let Some(Reverse(smallest)) = heap.peek() {
+                largest
+                    .threshold
+                    .store(smallest.apparent_bytes, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// The files kept by the largest-files pass, largest first.
+    pub fn largest_files(&self) -> Vec<TrackedFile> {
+        let Some(heap) = self
+            .largest_files
+            .as_ref()
+            .and_then(|largest| largest.heap.lock().ok())
+        else {
+            return Vec::new();
+        };
+        let mut kept: Vec<TrackedFile> = heap.iter().map(|Reverse(kept)| kept.clone()).collect();
+        kept.sort_by(|file_a, file_b| file_b.cmp(file_a));
+        kept
+    }
+}
+
+// Walks everything below `path`, fanning out across subdirectories so that
+// rayon can split deep trees between all available cores. Once the context
+// is cancelled, the walk stops descending and returns whatever it has summed
+// so far.
+pub fn walk_dir(path: &Path, context: &WalkContext) -> DirNode {
+    let name = path
+        .file_name()
+        .map(|os_name| os_name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let mut node = DirNode {
+        name,
+        ..DirNode::default()
+    };
+
+    if context.is_cancelled() {
+        return node;
+    }
+    // Like `du`, the directory's own blocks count towards disk usage.
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        node.allocated_bytes += allocated_len(&metadata);
+    }
+    let read_dir = match std::fs::read_dir(path) {
+        Ok(read_dir) => read_dir,
+        Err(read_error) => {
+            context.record_error(path, &read_error);
+            return node;
+        }
+    };
+    let entries: Vec<_> = read_dir
+        .filter_map(|entry_result| {
+            entry_result
+                .map_err(|entry_error| context.record_error(path, &entry_error))
+                .ok()
+        })
+        .collect();
+
+    let visited: Vec<EntryVisit> = entries
+        .par_iter()
+        .map(|dir_entry| match dir_entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => {
+                let child_path = dir_entry.path();
+                if context.crosses_mount(&child_path) {
+                    EntryVisit::Skipped
+                } else {
+                    EntryVisit::Dir(walk_dir(&child_path, context))
+                }
+            }
+            Ok(file_type) if file_type.is_file() => match dir_entry.metadata() {
+                Ok(metadata) => {
+                    context.offer_file(dir_entry, &metadata);
+                    EntryVisit::File(metadata)
+                }
+                Err(metadata_error) => {
+                    context.record_error(path, &metadata_error);
+                    EntryVisit::Skipped
+                }
+            },
+            Ok(_) => EntryVisit::Skipped,
+            Err(file_type_error) => {
+                context.record_error(path, &file_type_error);
+                EntryVisit::Skipped
+            }
+        })
+        .collect();
+
+    for visit in visited {
+        match visit {
+            EntryVisit::Dir(child) => {
+                node.add_totals(&child);
+                if context
+                    .retain_children
+                    .is_some_and(|min_bytes| child.apparent_bytes >= min_bytes)
+                {
+                    node.children.push(child);
+                }
+            }
+            EntryVisit::File(metadata) => context.add_file(&mut node, &metadata),
+            EntryVisit::Skipped => {}
+        }
+    }
+
+    node.children
+        .sort_by_key(|child| std::cmp::Reverse(child.apparent_bytes));
+    node
+}
+
+enum EntryVisit {
+    Dir(DirNode),
+    File(Metadata),
+    Skipped,
+}
+
+pub fn dir_size(path: &Path, cancel: &CancelToken) -> u64 {
+    walk_dir(path, &WalkContext::new(cancel)).apparent_bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::errors::ScanErrorKind;
+    use std::fs;
+
+    #[test]
+    fn sums_files_across_nested_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let nested = temp_dir.path().join("a/b/c");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(temp_dir.path().join("top.bin"), vec![0_u8; 100]).unwrap();
+        fs::write(temp_dir.path().join("a/mid.bin"), vec![0_u8; 20]).unwrap();
+        fs::write(nested.join("deep.bin"), vec![0_u8; 3]).unwrap();
+
+        assert_eq!(dir_size(temp_dir.path(), &CancelToken::new()), 123);
+    }
+
+    #[test]
+    fn missing_directory_counts_as_empty() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            dir_size(&temp_dir.path().join("gone"), &CancelToken::new()),
+            0
+        );
+    }
+
+    #[test]
+    fn retained_walk_keeps_children_largest_first() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("small")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("big/inner")).unwrap();
+        fs::write(temp_dir.path().join("small/a.bin"), vec![0_u8; 5]).unwrap();
+        fs::write(temp_dir.path().join("big/inner/b.bin"), vec![0_u8; 50]).unwrap();
+        fs::write(temp_dir.path().join("big/c.bin"), vec![0_u8; 7]).unwrap();
+
+        let node = walk_dir(
+            temp_dir.path(),
+            &WalkContext::new(&CancelToken::new()).retaining_children(),
+        );
+        assert_eq!(node.apparent_bytes, 62);
+        assert_eq!(node.file_count, 3);
+
+        let child_names: Vec<_> = node
+            .children
+            .iter()
+            .map(|child| child.name.as_str())
+            .collect();
+        assert_eq!(child_names, vec!["big", "small"]);
+        assert_eq!(node.children[0].children[0].name, "inner");
+        assert_eq!(node.children[0].children[0].apparent_bytes, 50);
+
+        let flat = walk_dir(temp_dir.path(), &WalkContext::new(&CancelToken::new()));
+        assert_eq!(flat.apparent_bytes, 62);
+        assert!(flat.children.is_empty());
+
+        let above = walk_dir(
+            temp_dir.path(),
+            &WalkContext::new(&CancelToken::new()).retaining_children_above(10),
+        );
+        assert_eq!(above.apparent_bytes, 62);
+        let kept: Vec<_> = above
+            .children
+            .iter()
+            .map(|child| child.name.as_str())
+            .collect();
+        assert_eq!(kept, vec!["big"]);
+        assert_eq!(above.children[0].children.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sparse_files_allocate_less_than_their_length() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let sparse = fs::File::create(temp_dir.path().join("disk.img")).unwrap();
+        sparse.set_len(64 * 1024 * 1024).unwrap();
+
+        let node = walk_dir(temp_dir.path(), &WalkContext::new(&CancelToken::new()));
+        assert_eq!(node.apparent_bytes, 64 * 1024 * 1024);
+        assert!(node.allocated_bytes < 1024 * 1024);
+        assert_eq!(node.size_bytes(SizeMode::Allocated), node.allocated_bytes);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn small_files_allocate_whole_blocks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("tiny.txt"), b"x").unwrap();
+
+        let node = walk_dir(temp_dir.path(), &WalkContext::new(&CancelToken::new()));
+        assert_eq!(node.apparent_bytes, 1);
+        assert!(node.allocated_bytes >= 512);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_are_charged_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("store")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("project/node_modules")).unwrap();
+        let original = temp_dir.path().join("store/pkg.tgz");
+        fs::write(&original, vec![0_u8; 1000]).unwrap();
+        fs::hard_link(
+            &original,
+            temp_dir.path().join("project/node_modules/pkg.tgz"),
+        )
+        .unwrap();
+        fs::hard_link(&original, temp_dir.path().join("store/pkg-copy.tgz")).unwrap();
+        fs::write(temp_dir.path().join("store/own.txt"), vec![0_u8; 10]).unwrap();
+
+        let node = walk_dir(temp_dir.path(), &WalkContext::new(&CancelToken::new()));
+        assert_eq!(node.apparent_bytes, 1010);
+        assert_eq!(node.shared_bytes, 1000);
+        assert!(node.shared_allocated_bytes >= 1000);
+        assert_eq!(node.file_count, 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn separate_contexts_each_charge_the_link() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let first = temp_dir.path().join("a");
+        let second = temp_dir.path().join("b");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::write(first.join("blob"), vec![0_u8; 100]).unwrap();
+        fs::hard_link(first.join("blob"), second.join("blob")).unwrap();
+
+        let cancel = CancelToken::new();
+        let shared_context = WalkContext::new(&cancel);
+        let first_node = walk_dir(&first, &shared_context);
+        let second_node = walk_dir(&second, &shared_context);
+        assert_eq!(first_node.apparent_bytes + second_node.apparent_bytes, 100);
+
+        assert_eq!(dir_size(&first, &cancel), 100);
+        assert_eq!(dir_size(&second, &cancel), 100);
+    }
+
+    #[test]
+    fn same_filesystem_walk_skips_nothing_locally() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("a/b")).unwrap();
+        fs::write(temp_dir.path().join("a/b/file.bin"), vec![0_u8; 9]).unwrap();
+
+        let cancel = CancelToken::new();
+        let context = WalkContext::new(&cancel).same_filesystem_as(temp_dir.path());
+        let node = walk_dir(temp_dir.path(), &context);
+        assert_eq!(node.apparent_bytes, 9);
+        assert!(context.skipped_mounts().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directories_on_another_device_are_skipped_and_reported() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("mnt")).unwrap();
+        fs::write(temp_dir.path().join("mnt/remote.bin"), vec![0_u8; 50]).unwrap();
+        fs::write(temp_dir.path().join("local.bin"), vec![0_u8; 5]).unwrap();
+
+        let cancel = CancelToken::new();
+        let mut context = WalkContext::new(&cancel);
+        // Pretend the root sits on some other device, which makes every
+        // subdirectory look like a mount point.
+        context.root_device = Some(u64::MAX);
+
+        let node = walk_dir(temp_dir.path(), &context);
+        assert_eq!(node.apparent_bytes, 5);
+
+        let skipped_mounts = context.skipped_mounts();
+        assert_eq!(skipped_mounts.len(), 1);
+        assert_eq!(
+            skipped_mounts[0].path,
+            temp_dir.path().join("mnt").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn missing_directory_is_reported() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let gone = temp_dir.path().join("gone");
+
+        let cancel = CancelToken::new();
+        let context = WalkContext::new(&cancel);
+        walk_dir(&gone, &context);
+
+        let errors = context.scan_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, gone.to_string_lossy());
+        assert_eq!(errors[0].kind, ScanErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_directory_is_reported_and_the_rest_still_counts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let locked = temp_dir.path().join("locked");
+        fs::create_dir_all(&locked).unwrap();
+        fs::write(locked.join("secret.bin"), vec![0_u8; 100]).unwrap();
+        fs::write(temp_dir.path().join("open.bin"), vec![0_u8; 7]).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        let cancel = CancelToken::new();
+        let context = WalkContext::new(&cancel);
+        let node = walk_dir(temp_dir.path(), &context);
+        let readable_anyway = fs::read_dir(&locked).is_ok();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Root can read anything, in which case there is nothing to report.
+        if readable_anyway {
+            return;
+        }
+        assert_eq!(node.apparent_bytes, 7);
+        let errors = context.scan_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ScanErrorKind::PermissionDenied);
+        assert_eq!(errors[0].path, locked.to_string_lossy());
+    }
+
+    #[test]
+    fn cancelled_walk_stops_early() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("file.bin"), vec![0_u8; 10]).unwrap();
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        assert_eq!(dir_size(temp_dir.path(), &cancel), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_symlinked_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("big.bin"), vec![0_u8; 4096]).unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("link")).unwrap();
+
+        assert_eq!(dir_size(temp_dir.path(), &CancelToken::new()), 0);
+    }
+}
@@ -0,0 +1,763 @@
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::audit::{AuditEntry, AuditLog, AuditWriter};
+use super::cancel::CancelToken;
+use super::category::Category;
+use super::crawler::device_id;
+use super::duplicates::{hash_file, DuplicateGroup};
+use super::guard::PathGuard;
+use super::mounts::MountTable;
+use super::purge::{next_plan_id, unix_now, PurgeItemResult, PurgeOutcome, RefusedTarget};
+use super::rules::RuleSet;
+
+/// Filesystems that can share extents between files with `FICLONE`.
+const REFLINK_FILESYSTEMS: &[&str] = &["btrfs", "xfs", "bcachefs"];
+
+// -- Shared types --
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMethod {
+    /// The copy becomes another name for the kept file. Writing through any
+    /// name changes them all.
+    HardLink,
+    /// The copy shares the kept file's extents but stays its own file;
+    /// writing to it later copies just the changed blocks.
+    Reflink,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DedupeStep {
+    pub path: String,
+    pub method: LinkMethod,
+    pub category: Category,
+    pub warnings: Vec<String>,
+}
+
+/// The dry-run half of a dedupe: which copies would be replaced by links to
+/// the kept one, and how.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DedupePlan {
+    pub plan_id: String,
+    pub created_at: u64,
+    pub hash: String,
+    pub size_bytes: u64,
+    pub keep: String,
+    pub steps: Vec<DedupeStep>,
+    pub reclaimable_bytes: u64,
+    /// Copies left out of the plan, each with the reason.
+    pub refused: Vec<RefusedTarget>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DedupeItemResult {
+    pub path: String,
+    pub method: LinkMethod,
+    pub outcome: PurgeOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DedupeReport {
+    pub plan_id: String,
+    pub items: Vec<DedupeItemResult>,
+    pub linked_bytes: u64,
+    /// Set when a step failed and every replaced copy was put back.
+    pub rolled_back: bool,
+    /// Problems that did not undo the dedupe, such as a backup that could
+    /// not be removed afterwards.
+    pub warnings: Vec<String>,
+}
+
+/// Creates `destination` as a link to `source`. The real implementation
+/// asks the filesystem; tests substitute their own.
+pub trait Linker: Send + Sync {
+    fn link(&self, method: LinkMethod, source: &Path, destination: &Path) -> io::Result<()>;
+}
+
+pub struct SystemLinker;
+
+impl Linker for SystemLinker {
+    fn link(&self, method: LinkMethod, source: &Path, destination: &Path) -> io::Result<()> {
+        match method {
+            LinkMethod::HardLink => fs::hard_link(source, destination),
+            LinkMethod::Reflink => reflink(source, destination),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(source: &Path, destination: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // _IOW(0x94, 9, int) from <linux/fs.h>.
+    const FICLONE: u32 = 0x4004_9409;
+
+    let source_file = fs::File::open(source)?;
+    let destination_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)?;
+    // SAFETY: both descriptors stay open for the duration of the call and
+    // FICLONE reads no memory from us.
+    let status = unsafe {
+        libc::ioctl(
+            destination_file.as_raw_fd(),
+            FICLONE as _,
+            source_file.as_raw_fd(),
+        )
+    };
+    if status == -1 {
+        let clone_error = io::Error::last_os_error();
+        let _ = fs::remove_file(destination);
+        return Err(clone_error);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_source: &Path, _destination: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reflinks are only supported on Linux",
+    ))
+}
+
+// -- Planning --
+
+fn link_method(keep: &Path, mounts: &MountTable) -> LinkMethod {
+    let location = keep.canonicalize().unwrap_or_else(|_| keep.to_path_buf());
+    if REFLINK_FILESYSTEMS.contains(&mounts.fs_type(&location).as_str()) {
+        LinkMethod::Reflink
+    } else {
+        LinkMethod::HardLink
+    }
+}
+
+#[cfg(unix)]
+fn permissions_differ(copy: &Metadata, kept: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    (copy.mode(), copy.uid(), copy.gid()) != (kept.mode(), kept.uid(), kept.gid())
+}
+
+#[cfg(not(unix))]
+fn permissions_differ(copy: &Metadata, kept: &Metadata) -> bool {
+    copy.permissions() != kept.permissions()
+}
+
+/// Decides how each copy in `group` would be replaced by a link to `keep`
+/// (the group's first path by default) without touching any of them. The
+/// copies must still be regular files of the group's size on the same
+/// filesystem as the kept one; those that are not, or that the guard
+/// refuses, are listed in `refused`.
+pub fn plan_dedupe(
+    group: &DuplicateGroup,
+    keep: Option<&str>,
+    guard: &PathGuard,
+    rules: &RuleSet,
+    mounts: &MountTable,
+) -> Result<DedupePlan, String> {
+    let keep = keep
+        .or(group.paths.first().map(String::as_str))
+        .ok_or("The duplicate group is empty")?;
+    if !group.paths.iter().any(|path| path == keep) {
+        return Err(format!("{keep} is not part of this duplicate group"));
+    }
+    let keep_path = Path::new(keep);
+    let keep_metadata =
+        fs::metadata(keep_path).map_err(|metadata_error| format!("{keep}: {metadata_error}"))?;
+    let method = link_method(keep_path, mounts);
+
+    let mut steps = Vec::new();
+    let mut refused = Vec::new();
+    for path in group.paths.iter().filter(|path| *path != keep) {
+        let copy_path = Path::new(path);
+        let refuse = |reason: String| RefusedTarget {
+            path: path.clone(),
+            reason,
+        };
+        if let Err(reason) = guard.check(copy_path) {
+            refused.push(refuse(reason));
+            continue;
+        }
+        let metadata = match fs::symlink_metadata(copy_path) {
+            Ok(metadata) => metadata,
+            Err(metadata_error) => {
+                refused.push(refuse(metadata_error.to_string()));
+                continue;
+            }
+        };
+        if !metadata.is_file() || metadata.len() != group.size_bytes {
+            refused.push(refuse(format!("{path} changed since the duplicate scan")));
+            continue;
+        }
+        if device_id(&metadata) != device_id(&keep_metadata) {
+            refused.push(refuse(format!(
+                "{path} is on a different filesystem than {keep}"
+            )));
+            continue;
+        }
+
+        let mut warnings = Vec::new();
+        if method == LinkMethod::HardLink {
+            warnings.push(format!(
+                "Becomes the same file as {keep}; editing either one changes both"
+            ));
+            if permissions_differ(&metadata, &keep_metadata) {
+                warnings.push(format!("Takes the owner and permissions of {keep}"));
+            }
+        }
+        steps.push(DedupeStep {
+            path: path.clone(),
+            method,
+            category: rules.classify(copy_path).category,
+            warnings,
+        });
+    }
+
+    Ok(DedupePlan {
+        plan_id: next_plan_id(),
+        created_at: unix_now(),
+        hash: group.hash.clone(),
+        size_bytes: group.size_bytes,
+        keep: keep.to_string(),
+        reclaimable_bytes: group.size_bytes * steps.len() as u64,
+        steps,
+        refused,
+    })
+}
+
+// -- Executing --
+
+/// A sibling name in the same directory, so renames to and from it never
+/// cross a filesystem.
+fn sibling(path: &Path, plan_id: &str, purpose: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|os_name| os_name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.sunder-{purpose}-{plan_id}"))
+}
+
+/// A copy that has been replaced, with the original kept aside until the
+/// whole plan is done.
+struct Replaced {
+    path: PathBuf,
+    backup: PathBuf,
+}
+
+/// Replaces one copy: the link is built under a temporary name, the copy is
+/// moved aside and the link renamed into its place. On error nothing of
+/// this step is left behind.
+fn replace_copy(
+    step: &DedupeStep,
+    keep: &Path,
+    plan_id: &str,
+    linker: &dyn Linker,
+) -> io::Result<Replaced> {
+    let path = PathBuf::from(&step.path);
+    let staged = sibling(&path, plan_id, "link");
+    let backup = sibling(&path, plan_id, "backup");
+
+    linker.link(step.method, keep, &staged)?;
+    if step.method == LinkMethod::Reflink {
+        // A clone starts out with default permissions; the copy's own are
+        // the ones its users expect.
+        if let Err(permissions_error) = fs::metadata(&path)
+            .and_then(|metadata| fs::set_permissions(&staged, metadata.permissions()))
+        {
+            let _ = fs::remove_file(&staged);
+            return Err(permissions_error);
+        }
+    }
+    if let Err(rename_error) = fs::rename(&path, &backup) {
+        let _ = fs::remove_file(&staged);
+        return Err(rename_error);
+    }
+    if let Err(rename_error) = fs::rename(&staged, &path) {
+        let _ = fs::rename(&backup, &path);
+        let _ = fs::remove_file(&staged);
+        return Err(rename_error);
+    }
+    Ok(Replaced { path, backup })
+}
+
+/// Puts every replaced copy back, newest first. Returns the copies that
+/// could not be restored.
+fn roll_back(replaced: &[Replaced]) -> Vec<String> {
+    replaced
+        .iter()
+        .rev()
+        .filter_map(|copy| {
+            fs::rename(&copy.backup, &copy.path)
+                .err()
+                .map(|rename_error| {
+                    format!(
+                        "{} could not be put back from {}: {rename_error}",
+                        copy.path.display(),
+                        copy.backup.display()
+                    )
+                })
+        })
+        .collect()
+}
+
+fn verify_hash(path: &Path, expected: &str, cancel: &CancelToken) -> Result<(), String> {
+    match hash_file(path, cancel) {
+        Ok(Some(hash)) if hash == expected => Ok(()),
+        Ok(Some(_)) => Err(format!(
+            "Refusing to dedupe: {} changed since the plan was made. Review a new plan first.",
+            path.display()
+        )),
+        Ok(None) => Err("Dedupe cancelled before anything was changed".into()),
+        Err(read_error) => Err(format!("{}: {read_error}", path.display())),
+    }
+}
+
+fn audit_entry(
+    plan: &DedupePlan,
+    step_index: usize,
+    entry_index: usize,
+    outcome: PurgeOutcome,
+    error: Option<String>,
+) -> AuditEntry {
+    let step = &plan.steps[step_index];
+    AuditEntry::new(
+        &plan.plan_id,
+        entry_index,
+        unix_now(),
+        &PurgeItemResult {
+            path: step.path.clone(),
+            size_bytes: plan.size_bytes,
+            category: step.category.clone(),
+            outcome,
+            error,
+            trash_location: None,
+        },
+    )
+}
+
+/// Puts every replaced copy back after the audit log failed partway, then
+/// logs the copies it had already recorded as linked a second time, as
+/// rolled back, so the log does not claim links that are gone. Returns the
+/// error to report.
+fn abandon_after_audit_error(
+    plan: &DedupePlan,
+    logged: &[DedupeItemResult],
+    replaced: &[Replaced],
+    audit_writer: &mut AuditWriter,
+    audit_error: String,
+) -> String {
+    let mut problems = vec![audit_error];
+    problems.extend(roll_back(replaced));
+
+    // Entry ids must stay unique, so corrections are numbered after the
+    // plan's own entries.
+    for (index, item) in logged.iter().enumerate() {
+        if item.outcome != PurgeOutcome::Linked {
+            continue;
+        }
+        let correction = audit_entry(
+            plan,
+            index,
+            plan.steps.len() + index,
+            PurgeOutcome::RolledBack,
+            Some("Put back because the audit log could not be written".into()),
+        );
+        if let Err(correction_error) = audit_writer.append(&correction) {
+            problems.push(format!(
+                "the audit log still lists {} as linked: {correction_error}",
+                item.path
+            ));
+            break;
+        }
+    }
+
+    if problems.len() == 1 {
+        format!("{}; every copy was put back", problems[0])
+    } else {
+        problems.join("; ")
+    }
+}
+
+/// Replaces every copy in `plan` with a link to the kept file. Nothing is
+/// touched unless the audit log is writable, the kept file and every copy
+/// still pass the guard and every file still hashes to the planned content.
+/// If any copy cannot be replaced, or the result cannot be audited, the
+/// copies already replaced are put back and the report says so.
+pub fn execute_dedupe(
+    plan: &DedupePlan,
+    guard: &PathGuard,
+    linker: &dyn Linker,
+    audit: &AuditLog,
+    cancel: &CancelToken,
+) -> Result<DedupeReport, String> {
+    // The kept file is guarded too: every copy ends up sharing its data.
+    for path in std::iter::once(&plan.keep).chain(plan.steps.iter().map(|step| &step.path)) {
+        guard
+            .check(Path::new(path))
+            .map_err(|reason| format!("Refusing to dedupe: {reason}"))?;
+    }
+    let keep = Path::new(&plan.keep);
+    verify_hash(keep, &plan.hash, cancel)?;
+    for step in &plan.steps {
+        verify_hash(Path::new(&step.path), &plan.hash, cancel)?;
+    }
+
+    let mut audit_writer = audit.writer()?;
+    let mut replaced = Vec::new();
+    let mut failure: Option<(usize, String)> = None;
+    for (index, step) in plan.steps.iter().enumerate() {
+        match replace_copy(step, keep, &plan.plan_id, linker) {
+            Ok(copy) => replaced.push(copy),
+            Err(link_error) => {
+                failure = Some((index, link_error.to_string()));
+                break;
+            }
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let outcome_of = |index: usize| match &failure {
+        None => (PurgeOutcome::Linked, None),
+        Some((failed_index, link_error)) if *failed_index == index => {
+            (PurgeOutcome::Failed, Some(link_error.clone()))
+        }
+        Some((failed_index, _)) if index < *failed_index => (PurgeOutcome::RolledBack, None),
+        Some(_) => (
+            PurgeOutcome::Failed,
+            Some("Skipped after an earlier copy failed".to_string()),
+        ),
+    };
+    let items: Vec<DedupeItemResult> = plan
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| {
+            let (outcome, error) = outcome_of(index);
+            DedupeItemResult {
+                path: step.path.clone(),
+                method: step.method,
+                outcome,
+                error,
+            }
+        })
+        .collect();
+
+    if failure.is_some() {
+        warnings.extend(roll_back(&replaced));
+        replaced.clear();
+    }
+
+    for (index, item) in items.iter().enumerate() {
+        let entry = audit_entry(plan, index, index, item.outcome, item.error.clone());
+        if let Err(audit_error) = audit_writer.append(&entry) {
+            return Err(abandon_after_audit_error(
+                plan,
+                &items[..index],
+                &replaced,
+                &mut audit_writer,
+                audit_error,
+            ));
+        }
+    }
+
+    for copy in &replaced {
+        if let Err(remove_error) = fs::remove_file(&copy.backup) {
+            warnings.push(format!(
+                "{} could not be removed: {remove_error}",
+                copy.backup.display()
+            ));
+        }
+    }
+
+    Ok(DedupeReport {
+        plan_id: plan.plan_id.clone(),
+        linked_bytes: plan.size_bytes * replaced.len() as u64,
+        rolled_back: failure.is_some(),
+        items,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::progress::NoopSink;
+    use crate::engine::test_support::{open_guard, scratch_audit, write_contents};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Hard-links like the system, but fails the call numbered `fail_at`.
+    struct FlakyLinker {
+        calls: AtomicUsize,
+        fail_at: usize,
+    }
+
+    impl Linker for FlakyLinker {
+        fn link(&self, _method: LinkMethod, source: &Path, destination: &Path) -> io::Result<()> {
+            if self.calls.fetch_add(1, Ordering::Relaxed) == self.fail_at {
+                return Err(io::Error::other("disk on fire"));
+            }
+            fs::hard_link(source, destination)
+        }
+    }
+
+    struct Fixture {
+        root: tempfile::TempDir,
+        _data_dir: tempfile::TempDir,
+        audit: AuditLog,
+        guard: PathGuard,
+        group: DuplicateGroup,
+    }
+
+    fn fixture() -> Fixture {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["a/sdk.tar", "b/sdk.tar", "c/sdk.tar"] {
+            write_contents(&root.path().join(name), &[42; 10_000]);
+        }
+        let (data_dir, audit) = scratch_audit();
+        let guard = open_guard(root.path());
+        let scan = crate::engine::duplicates::find_duplicates(
+            root.path(),
+            1,
+            &NoopSink,
+            &CancelToken::new(),
+        );
+        let group = scan.groups[0].clone();
+        Fixture {
+            root,
+            _data_dir: data_dir,
+            audit,
+            guard,
+            group,
+        }
+    }
+
+    fn plan(fixture: &Fixture) -> DedupePlan {
+        plan_dedupe(
+            &fixture.group,
+            None,
+            &fixture.guard,
+            &RuleSet::builtin(),
+            &MountTable::default(),
+        )
+        .unwrap()
+    }
+
+    fn leftovers(root: &Path) -> Vec<String> {
+        walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_map(|entry_result| entry_result.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.contains(".sunder-"))
+            .collect()
+    }
+
+    #[test]
+    fn plan_keeps_the_first_copy_and_picks_a_method() {
+        let fixture = fixture();
+        let plan = plan(&fixture);
+        assert_eq!(plan.keep, fixture.group.paths[0]);
+        assert_eq!(plan.steps.len(), 2);
+        assert!(plan
+            .steps
+            .iter()
+            .all(|step| step.method == LinkMethod::HardLink));
+        assert_eq!(plan.reclaimable_bytes, 20_000);
+
+        let mountinfo = format!(
+            "36 1 0:32 / {} rw - btrfs /dev/sda2 rw",
+            fixture.root.path().canonicalize().unwrap().display()
+        );
+        let on_btrfs = plan_dedupe(
+            &fixture.group,
+            Some(&fixture.group.paths[2]),
+            &fixture.guard,
+            &RuleSet::builtin(),
+            &MountTable::parse_mountinfo(&mountinfo),
+        )
+        .unwrap();
+        assert_eq!(on_btrfs.keep, fixture.group.paths[2]);
+        assert!(on_btrfs
+            .steps
+            .iter()
+            .all(|step| step.method == LinkMethod::Reflink && step.warnings.is_empty()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn execute_links_every_copy_and_audits_it() {
+        use std::os::unix::fs::MetadataExt;
+
+        let fixture = fixture();
+        let plan = plan(&fixture);
+        let report = execute_dedupe(
+            &plan,
+            &fixture.guard,
+            &SystemLinker,
+            &fixture.audit,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert!(!report.rolled_back);
+        assert_eq!(report.linked_bytes, 20_000);
+        let inodes: Vec<u64> = fixture
+            .group
+            .paths
+            .iter()
+            .map(|path| fs::metadata(path).unwrap().ino())
+            .collect();
+        assert!(inodes.iter().all(|inode| *inode == inodes[0]));
+        assert!(leftovers(fixture.root.path()).is_empty());
+
+        let logged = fixture.audit.entries().unwrap();
+        assert_eq!(logged.len(), 2);
+        assert!(logged
+            .iter()
+            .all(|entry| entry.outcome == PurgeOutcome::Linked));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_failed_step_puts_every_copy_back() {
+        use std::os::unix::fs::MetadataExt;
+
+        let fixture = fixture();
+        let plan = plan(&fixture);
+        let linker = FlakyLinker {
+            calls: AtomicUsize::new(0),
+            fail_at: 1,
+        };
+        let report = execute_dedupe(
+            &plan,
+            &fixture.guard,
+            &linker,
+            &fixture.audit,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert!(report.rolled_back);
+        assert_eq!(report.linked_bytes, 0);
+        let outcomes: Vec<PurgeOutcome> = report.items.iter().map(|item| item.outcome).collect();
+        assert_eq!(outcomes, [PurgeOutcome::RolledBack, PurgeOutcome::Failed]);
+        for path in &fixture.group.paths {
+            assert_eq!(fs::metadata(path).unwrap().nlink(), 1);
+            assert_eq!(fs::read(path).unwrap(), vec![42_u8; 10_000]);
+        }
+        assert!(leftovers(fixture.root.path()).is_empty());
+        assert_eq!(fixture.audit.entries().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn execute_refuses_when_a_copy_changed() {
+        let fixture = fixture();
+        let plan = plan(&fixture);
+        let mut edited = vec![42_u8; 10_000];
+        edited[5_000] = 0;
+        fs::write(&plan.steps[1].path, &edited).unwrap();
+
+        let refusal = execute_dedupe(
+            &plan,
+            &fixture.guard,
+            &SystemLinker,
+            &fixture.audit,
+            &CancelToken::new(),
+        )
+        .unwrap_err();
+        assert!(refusal.contains("changed since the plan was made"));
+        assert_eq!(fs::read(&plan.steps[1].path).unwrap(), edited);
+        assert!(fixture.audit.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn execute_guards_the_kept_file() {
+        let fixture = fixture();
+        let plan = plan(&fixture);
+        let keep_dir = Path::new(&plan.keep).parent().unwrap();
+        let guard = PathGuard::new(
+            None,
+            Some(fixture.root.path()),
+            &[keep_dir.to_string_lossy().to_string()],
+        );
+
+        let refusal = execute_dedupe(
+            &plan,
+            &guard,
+            &SystemLinker,
+            &fixture.audit,
+            &CancelToken::new(),
+        )
+        .unwrap_err();
+        assert!(refusal.contains("protected"));
+        assert!(fixture.audit.entries().unwrap().is_empty());
+    }
+
+    /// `/dev/full` opens fine and fails every write.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn an_unwritable_audit_puts_every_copy_back() {
+        use std::os::unix::fs::MetadataExt;
+
+        let fixture = fixture();
+        let plan = plan(&fixture);
+        let audit_error = execute_dedupe(
+            &plan,
+            &fixture.guard,
+            &SystemLinker,
+            &AuditLog::new("/dev/full"),
+            &CancelToken::new(),
+        )
+        .unwrap_err();
+
+        assert!(audit_error.contains("Audit log write failed"));
+        for path in &fixture.group.paths {
+            assert_eq!(fs::metadata(path).unwrap().nlink(), 1);
+        }
+        assert!(leftovers(fixture.root.path()).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn entries_logged_before_an_audit_failure_are_corrected() {
+        use std::os::unix::fs::MetadataExt;
+
+        let fixture = fixture();
+        let plan = plan(&fixture);
+        let keep = Path::new(&plan.keep);
+        let replaced =
+            vec![replace_copy(&plan.steps[0], keep, &plan.plan_id, &SystemLinker).unwrap()];
+        let mut audit_writer = fixture.audit.writer().unwrap();
+        let linked = audit_entry(&plan, 0, 0, PurgeOutcome::Linked, None);
+        audit_writer.append(&linked).unwrap();
+
+        let logged = [DedupeItemResult {
+            path: plan.steps[0].path.clone(),
+            method: plan.steps[0].method,
+            outcome: PurgeOutcome::Linked,
+            error: None,
+        }];
+        let error = abandon_after_audit_error(
+            &plan,
+            &logged,
+            &replaced,
+            &mut audit_writer,
+            "disk full".into(),
+        );
+        assert_eq!(error, "disk full; every copy was put back");
+        assert_eq!(fs::metadata(&plan.steps[0].path).unwrap().nlink(), 1);
+
+        let entries = fixture.audit.entries().unwrap();
+        let outcomes: Vec<(&str, PurgeOutcome)> = entries
+            .iter()
+            .map(|entry| (entry.id.rsplit('/').next().unwrap(), entry.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [("0", PurgeOutcome::Linked), ("2", PurgeOutcome::RolledBack)]
+        );
+    }
+}
@@ -0,0 +1,441 @@
+use rayon::prelude::*;
+use std::path::PathBuf;
+
+use super::audit::AuditLog;
+use super::cancel::CancelToken;
+use super::category::Category;
+use super::cleanup::{
+    clean_target, preview_strategy, CleanupPreview, CleanupReport, CleanupStrategy, CleanupTarget,
+    ToolCommand, ToolRunner, FOLDER_PLACEHOLDER, WHOLE_FOLDER,
+};
+use super::guard::PathGuard;
+use super::purge::Trasher;
+use super::xdg::XdgDirs;
+
+// -- Known caches --
+
+/// How much it costs to get a cache's contents back after cleaning it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegenerationCost {
+    /// Refilled quickly by the next install or build.
+    Low,
+    /// Noticeably slower builds or large downloads the next time.
+    Medium,
+    /// Full rebuilds or re-pulls that can take a long time.
+    High,
+}
+
+/// Where a cache lives, relative to the base directory its tool puts it in.
+/// Tools that follow the XDG spec move along with `$XDG_CACHE_HOME` and
+/// `$XDG_DATA_HOME`.
+enum CacheLocation {
+    Home(&'static str),
+    CacheHome(&'static str),
+    DataHome(&'static str),
+}
+
+impl CacheLocation {
+    fn resolve(&self, dirs: &XdgDirs) -> PathBuf {
+        match self {
+            Self::Home(path) => dirs.home.join(path),
+            Self::CacheHome(path) => dirs.cache_home.join(path),
+            Self::DataHome(path) => dirs.data_home.join(path),
+        }
+    }
+}
+
+/// A developer tool cache at a fixed place in the user's directories.
+struct DevCache {
+    id: &'static str,
+    name: &'static str,
+    location: CacheLocation,
+    description: &'static str,
+    regeneration_cost: RegenerationCost,
+    regeneration_hint: &'static str,
+    strategy: CleanupStrategy,
+}
+
+const DEV_CACHES: &[DevCache] = &[
+    DevCache {
+        id: "pip",
+        name: "pip",
+        location: CacheLocation::CacheHome("pip"),
+        description: "Downloaded packages and built wheels kept by pip",
+        regeneration_cost: RegenerationCost::Low,
+        regeneration_hint: "Packages are downloaded again on the next pip install",
+        strategy: CleanupStrategy {
+            folder_name: "pip",
+            tool: Some(ToolCommand {
+                program: "pip",
+                args: &["cache", "purge"],
+                env: &[("PIP_CACHE_DIR", FOLDER_PLACEHOLDER)],
+            }),
+            safe_subdirs: &[WHOLE_FOLDER],
+            caution_subdirs: &[],
+            notes: "Installed packages and virtual environments are not affected",
+        },
+    },
+    DevCache {
+        id: "pypoetry",
+        name: "Poetry",
+        location: CacheLocation::CacheHome("pypoetry"),
+        description: "Poetry's package cache, artifacts and project virtualenvs",
+        regeneration_cost: RegenerationCost::Low,
+        regeneration_hint: "Packages are downloaded again on the next poetry install",
+        strategy: CleanupStrategy {
+            folder_name: "pypoetry",
+            tool: None,
+            safe_subdirs: &["cache", "artifacts"],
+            caution_subdirs: &[],
+            notes: "Keeps the virtualenvs Poetry created for your projects",
+        },
+    },
+    DevCache {
+        id: "go-build",
+        name: "Go build cache",
+        location: CacheLocation::CacheHome("go-build"),
+        description: "Compiled packages and test results cached by go build",
+        regeneration_cost: RegenerationCost::Medium,
+        regeneration_hint: "The next build of each Go project recompiles from scratch",
+        strategy: CleanupStrategy {
+            folder_name: "go-build",
+            tool: Some(ToolCommand {
+                program: "go",
+                args: &["clean", "-cache"],
+                env: &[("GOCACHE", FOLDER_PLACEHOLDER)],
+            }),
+            safe_subdirs: &[WHOLE_FOLDER],
+            caution_subdirs: &[],
+            notes: "Only cached build output is removed",
+        },
+    },
+    DevCache {
+        id: "go-mod",
+        name: "Go module cache",
+        location: CacheLocation::Home("go/pkg/mod"),
+        description: "Source of every Go module version downloaded",
+        regeneration_cost: RegenerationCost::Medium,
+        regeneration_hint: "Modules are downloaded again when a project next builds",
+        strategy: CleanupStrategy {
+            folder_name: "mod",
+            tool: Some(ToolCommand {
+                program: "go",
+                args: &["clean", "-modcache"],
+                env: &[("GOMODCACHE", FOLDER_PLACEHOLDER)],
+            }),
+            safe_subdirs: &[WHOLE_FOLDER],
+            caution_subdirs: &[],
+            notes: "Installed binaries in ~/go/bin are kept",
+        },
+    },
+    DevCache {
+        id: "yarn",
+        name: "Yarn",
+        location: CacheLocation::CacheHome("yarn"),
+        description: "Yarn 1 offline package cache",
+        regeneration_cost: RegenerationCost::Low,
+        regeneration_hint: "Packages are downloaded again on the next yarn install",
+        strategy: CleanupStrategy {
+            folder_name: "yarn",
+            tool: Some(ToolCommand {
+                program: "yarn",
+                args: &["cache", "clean"],
+                env: &[("YARN_CACHE_FOLDER", FOLDER_PLACEHOLDER)],
+            }),
+            safe_subdirs: &[WHOLE_FOLDER],
+            caution_subdirs: &[],
+            notes: "Global installs and project lockfiles are not affected",
+        },
+    },
+    DevCache {
+        id: "bazel",
+        name: "Bazel",
+        location: CacheLocation::CacheHome("bazel"),
+        description: "Bazel output bases, repository cache and install base",
+        regeneration_cost: RegenerationCost::High,
+        regeneration_hint: "Every Bazel workspace rebuilds and refetches its dependencies",
+        strategy: CleanupStrategy {
+            folder_name: "bazel",
+            // `bazel clean --expunge` only covers one workspace at a time.
+            tool: None,
+            safe_subdirs: &[WHOLE_FOLDER],
+            caution_subdirs: &[],
+            notes: "Run bazel shutdown in open workspaces first",
+        },
+    },
+    DevCache {
+        id: "containers",
+        name: "Podman storage",
+        location: CacheLocation::DataHome("containers"),
+        description: "Rootless Podman images, containers and volumes",
+        regeneration_cost: RegenerationCost::High,
+        regeneration_hint: "Pruned images are pulled or rebuilt again when needed",
+        strategy: CleanupStrategy {
+            folder_name: "containers",
+            // `podman system prune` removes resources nobody reviewed; the
+            // container engine section prunes a listed selection instead.
+            tool: None,
+            // Container storage is never trashed by hand; volumes hold data.
+            safe_subdirs: &[],
+            caution_subdirs: &[],
+            notes: "Prune images and containers from the container engine section",
+        },
+    },
+    DevCache {
+        id: "docker",
+        name: "Docker",
+        location: CacheLocation::Home(".docker"),
+        description: "Docker CLI config, contexts and Docker Desktop data",
+        regeneration_cost: RegenerationCost::High,
+        regeneration_hint: "Pruned images are pulled or rebuilt again when needed",
+        strategy: CleanupStrategy {
+            folder_name: ".docker",
+            // Same as Podman: no blind `docker system prune`.
+            tool: None,
+            safe_subdirs: &[],
+            caution_subdirs: &[],
+            notes: "Prune images, containers and build cache from the container engine section",
+        },
+    },
+    DevCache {
+        id: "colima",
+        name: "Colima",
+        location: CacheLocation::Home(".colima"),
+        description: "Colima virtual machines and their cached downloads",
+        regeneration_cost: RegenerationCost::Medium,
+        regeneration_hint: "VM images are downloaded again when an instance is created",
+        strategy: CleanupStrategy {
+            folder_name: ".colima",
+            tool: Some(ToolCommand {
+                program: "colima",
+                args: &["prune", "--force"],
+                env: &[],
+            }),
+            safe_subdirs: &[],
+            caution_subdirs: &[],
+            notes: "Only cached downloads are pruned; VM disks need colima delete",
+        },
+    },
+];
+
+// -- Shared types --
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DevPurgeEntry {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub regeneration_cost: RegenerationCost,
+    pub regeneration_hint: String,
+    /// Where the cache is, its size and what cleaning it would do.
+    pub cleanup: CleanupPreview,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DevPurgeScan {
+    /// Caches that exist, largest first.
+    pub entries: Vec<DevPurgeEntry>,
+    pub total_size_bytes: u64,
+    pub cancelled: bool,
+}
+
+// -- Scanning and cleaning --
+
+/// Sizes the known developer caches in `dirs` and previews their cleanup.
+pub fn scan_dev_caches(
+    dirs: &XdgDirs,
+    tools: &dyn ToolRunner,
+    cancel: &CancelToken,
+) -> DevPurgeScan {
+    let mut entries: Vec<DevPurgeEntry> = DEV_CACHES
+        .par_iter()
+        .filter_map(|cache| {
+            let folder = cache.location.resolve(dirs);
+            if cancel.is_cancelled() || !folder.is_dir() {
+                return None;
+            }
+            Some(DevPurgeEntry {
+                id: cache.id.to_string(),
+                name: cache.name.to_string(),
+                description: cache.description.to_string(),
+                regeneration_cost: cache.regeneration_cost,
+                regeneration_hint: cache.regeneration_hint.to_string(),
+                cleanup: preview_strategy(&folder, &cache.strategy, tools, cancel),
+            })
+        })
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.cleanup.size_bytes));
+
+    DevPurgeScan {
+        total_size_bytes: entries.iter().map(|entry| entry.cleanup.size_bytes).sum(),
+        entries,
+        cancelled: cancel.is_cancelled(),
+    }
+}
+
+/// Cleans the cache with `id` the same way package caches are cleaned: its
+/// tool first, then its safe subdirectories.
+pub fn clean_dev_cache(
+    dirs: &XdgDirs,
+    id: &str,
+    guard: &PathGuard,
+    tools: &dyn ToolRunner,
+    trasher: &dyn Trasher,
+    audit: &AuditLog,
+    cancel: &CancelToken,
+) -> Result<CleanupReport, String> {
+    let cache = DEV_CACHES
+        .iter()
+        .find(|cache| cache.id == id)
+        .ok_or_else(|| format!("Unknown developer cache {id}"))?;
+    let folder = cache.location.resolve(dirs);
+    let target = CleanupTarget {
+        folder: &folder,
+        strategy: &cache.strategy,
+        category: Category::DeveloperCaches,
+    };
+    clean_target(&target, guard, tools, trasher, audit, cancel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_support::{scratch_audit, write_file, DeleteTrash, FakeTools};
+    use std::path::Path;
+
+    fn default_dirs(home: &Path) -> XdgDirs {
+        XdgDirs::resolve(home, |_| None)
+    }
+
+    #[test]
+    fn lists_existing_caches_largest_first() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join(".cache/pip/wheels/a.whl"), 100);
+        write_file(&home.path().join("go/pkg/mod/cache/download/x.zip"), 300);
+
+        let scan = scan_dev_caches(
+            &default_dirs(home.path()),
+            &FakeTools::none(),
+            &CancelToken::new(),
+        );
+        let ids: Vec<&str> = scan.entries.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["go-mod", "pip"]);
+        assert_eq!(scan.total_size_bytes, 400);
+        assert_eq!(
+            scan.entries[1].cleanup.tool_command.as_deref(),
+            Some("pip cache purge")
+        );
+        assert!(!scan.entries[1].cleanup.tool_available);
+    }
+
+    #[test]
+    fn caches_follow_xdg_cache_home() {
+        let home = tempfile::tempdir().unwrap();
+        let cache_home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join(".cache/pip/stale.whl"), 10);
+        write_file(&cache_home.path().join("pip/wheels/a.whl"), 100);
+        let dirs = XdgDirs::resolve(home.path(), |variable| {
+            (variable == "XDG_CACHE_HOME").then(|| cache_home.path().into())
+        });
+
+        let scan = scan_dev_caches(&dirs, &FakeTools::none(), &CancelToken::new());
+        assert_eq!(scan.entries.len(), 1);
+        assert_eq!(
+            scan.entries[0].cleanup.path,
+            cache_home.path().join("pip").to_string_lossy()
+        );
+        assert_eq!(scan.total_size_bytes, 100);
+    }
+
+    #[test]
+    fn cleans_safe_subdirectories_without_the_tool() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(
+            &home
+                .path()
+                .join(".cache/pypoetry/cache/repositories/pypi/x"),
+            80,
+        );
+        write_file(
+            &home
+                .path()
+                .join(".cache/pypoetry/virtualenvs/app-py3.12/bin/python"),
+            20,
+        );
+        let (_data_dir, audit) = scratch_audit();
+        let guard = PathGuard::new(Some(home.path()), Some(home.path()), &[]);
+
+        let report = clean_dev_cache(
+            &default_dirs(home.path()),
+            "pypoetry",
+            &guard,
+            &FakeTools::none(),
+            &DeleteTrash,
+            &audit,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(report.freed_bytes, 80);
+        assert!(home.path().join(".cache/pypoetry/virtualenvs").exists());
+        assert_eq!(
+            audit.entries().unwrap()[0].category,
+            Category::DeveloperCaches
+        );
+    }
+
+    #[test]
+    fn whole_folder_caches_are_trashed_entirely() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join(".cache/go-build/00/abc-d"), 64);
+        let (_data_dir, audit) = scratch_audit();
+        let guard = PathGuard::new(Some(home.path()), Some(home.path()), &[]);
+
+        let report = clean_dev_cache(
+            &default_dirs(home.path()),
+            "go-build",
+            &guard,
+            &FakeTools::none(),
+            &DeleteTrash,
+            &audit,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(report.freed_bytes, 64);
+        assert!(!home.path().join(".cache/go-build").exists());
+    }
+
+    #[test]
+    fn container_storage_is_left_to_the_engine_section() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(
+            &home
+                .path()
+                .join(".local/share/containers/storage/overlay/l"),
+            50,
+        );
+        let (_data_dir, audit) = scratch_audit();
+        let guard = PathGuard::new(Some(home.path()), Some(home.path()), &[]);
+
+        let tools = FakeTools::new(&["podman", "docker"]);
+
+        let report = clean_dev_cache(
+            &default_dirs(home.path()),
+            "containers",
+            &guard,
+            &tools,
+            &DeleteTrash,
+            &audit,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(report.freed_bytes, 0);
+        assert!(report.tool_run.is_none());
+        assert!(report.trashed.is_empty());
+        assert!(tools.calls.lock().unwrap().is_empty());
+        assert!(home.path().join(".local/share/containers/storage").exists());
+    }
+}
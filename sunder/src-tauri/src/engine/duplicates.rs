@@ -0,0 +1,345 @@
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use super::cancel::CancelToken;
+use super::crawler::{hard_link_key, WalkContext};
+use super::errors::ScanError;
+use super::mounts::SkippedMount;
+use super::progress::{FolderProgress, ProgressSink};
+
+/// Files smaller than this are not worth the reads when no minimum is given.
+pub const DEFAULT_MIN_SIZE: u64 = 1024 * 1024;
+
+/// Bytes read from each end of a file for the partial hash.
+const EDGE_BLOCK: u64 = 4096;
+
+const READ_BUFFER: usize = 1024 * 1024;
+
+/// Version control internals are full of identical small objects that are
+/// never safe to touch.
+const SKIPPED_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+// -- Shared types --
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DuplicateGroup {
+    /// BLAKE3 of the content, as hex.
+    pub hash: String,
+    /// Size of each copy.
+    pub size_bytes: u64,
+    /// Sorted, so the first path is a stable choice for the copy to keep.
+    pub paths: Vec<String>,
+    /// What removing every copy but one would free.
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DuplicateScan {
+    pub root: String,
+    /// Most reclaimable first.
+    pub groups: Vec<DuplicateGroup>,
+    pub total_reclaimable_bytes: u64,
+    pub skipped_mounts: Vec<SkippedMount>,
+    pub errors: Vec<ScanError>,
+    pub omitted_error_count: u64,
+    pub cancelled: bool,
+}
+
+// -- Hashing --
+
+fn hash_edges(path: &Path, size: u64) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut block = vec![0; EDGE_BLOCK.min(size) as usize];
+    file.read_exact(&mut block)?;
+    hasher.update(&block);
+    if size > EDGE_BLOCK {
+        let tail_len = EDGE_BLOCK.min(size - EDGE_BLOCK);
+        file.seek(SeekFrom::Start(size - tail_len))?;
+        block.truncate(tail_len as usize);
+        file.read_exact(&mut block)?;
+        hasher.update(&block);
+    }
+    Ok(*hasher.finalize().as_bytes())
+}
+
+/// The BLAKE3 hash of a whole file as hex, or `None` when cancelled
+/// partway through.
+pub fn hash_file(path: &Path, cancel: &CancelToken) -> io::Result<Option<String>> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0; READ_BUFFER];
+    loop {
+        if cancel.is_cancelled() {
+            return Ok(None);
+        }
+        let read_len = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(read_error) if read_error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(read_error) => return Err(read_error),
+        };
+        hasher.update(&buffer[..read_len]);
+    }
+    Ok(Some(hasher.finalize().to_hex().to_string()))
+}
+
+// -- Scan --
+
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Every regular file of at least `min_size` bytes below `root`. Extra hard
+/// links to an inode already listed are left out: they share its storage,
+/// so there is nothing to reclaim by removing them.
+fn discover(root: &Path, min_size: u64, context: &WalkContext) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let mut seen_inodes = HashSet::new();
+    let mut walker = WalkDir::new(root).follow_links(false).into_iter();
+
+    while let Some(entry_result) = walker.next() {
+        if context.is_cancelled() {
+            break;
+        }
+        let entry = match entry_result {
+            Ok(entry) => entry,
+            Err(walk_error) => {
+                if let (Some(path), Some(io_error)) = (walk_error.path(), walk_error.io_error()) {
+                    context.record_error(path, io_error);
+                }
+                continue;
+            }
+        };
+        if entry.file_type().is_dir() {
+            let name = entry.file_name().to_string_lossy();
+            if entry.depth() > 0
+                && (SKIPPED_DIRS.contains(&name.as_ref()) || context.crosses_mount(entry.path()))
+            {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(walk_error) => {
+                if let Some(io_error) = walk_error.io_error() {
+                    context.record_error(entry.path(), io_error);
+                }
+                continue;
+            }
+        };
+        if metadata.len() < min_size {
+            continue;
+        }
+        if let Some(link_key) = hard_link_key(&metadata) {
+            if !seen_inodes.insert(link_key) {
+                continue;
+            }
+        }
+        candidates.push(Candidate {
+            path: entry.into_path(),
+            size: metadata.len(),
+        });
+    }
+    candidates
+}
+
+/// Splits every group by `key`, keeping only the subgroups that still have
+/// more than one member, each with the key its members share. Members whose
+/// key cannot be computed are dropped.
+fn refine<K, F>(groups: Vec<Vec<Candidate>>, key: F) -> Vec<(K, Vec<Candidate>)>
+where
+    K: Eq + std::hash::Hash + Send,
+    F: Fn(&Candidate) -> Option<K> + Sync,
+{
+    groups
+        .into_par_iter()
+        .flat_map_iter(|group| {
+            let keyed: Vec<(Option<K>, Candidate)> = group
+                .into_par_iter()
+                .map(|candidate| (key(&candidate), candidate))
+                .collect();
+            let mut by_key: HashMap<K, Vec<Candidate>> = HashMap::new();
+            for (candidate_key, candidate) in keyed {
+                if let Some(candidate_key) = candidate_key {
+                    by_key.entry(candidate_key).or_default().push(candidate);
+                }
+            }
+            by_key
+                .into_iter()
+                .filter(|(_, subgroup)| subgroup.len() > 1)
+        })
+        .collect()
+}
+
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .map(|os_name| os_name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Finds files below `root` with identical content. Files are grouped by
+/// size, then by a hash of their first and last blocks, and only the
+/// survivors are read in full, so most files are never read at all.
+pub fn find_duplicates(
+    root: &Path,
+    min_size: u64,
+    sink: &dyn ProgressSink,
+    cancel: &CancelToken,
+) -> DuplicateScan {
+    let context = WalkContext::new(cancel).same_filesystem_as(root);
+    // Empty files are all "identical" and free nothing.
+    let candidates = discover(root, min_size.max(1), &context);
+
+    let mut by_size: HashMap<u64, Vec<Candidate>> = HashMap::new();
+    for candidate in candidates {
+        by_size.entry(candidate.size).or_default().push(candidate);
+    }
+    let size_groups: Vec<Vec<Candidate>> = by_size
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+
+    // Each file counts as one step, finished either when the partial hash
+    // rules it out or when its full hash is done.
+    let candidate_count: usize = size_groups.iter().map(Vec::len).sum();
+    let progress = FolderProgress::start(sink, candidate_count as u64);
+
+    let edge_groups: Vec<Vec<Candidate>> = refine(size_groups, |candidate| {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        hash_edges(&candidate.path, candidate.size)
+            .map_err(|read_error| context.record_error(&candidate.path, &read_error))
+            .ok()
+    })
+    .into_iter()
+    .map(|(_, group)| group)
+    .collect();
+    let surviving_count: usize = edge_groups.iter().map(Vec::len).sum();
+    progress.folders_done((candidate_count - surviving_count) as u64, "");
+
+    let full_groups = refine(edge_groups, |candidate| {
+        let hash = hash_file(&candidate.path, cancel)
+            .map_err(|read_error| context.record_error(&candidate.path, &read_error))
+            .ok()
+            .flatten();
+        progress.folder_done(&file_label(&candidate.path));
+        hash
+    });
+
+    let cancelled = cancel.is_cancelled();
+    if !cancelled {
+        progress.finish();
+    }
+
+    let mut groups: Vec<DuplicateGroup> = full_groups
+        .into_iter()
+        .map(|(hash, group)| {
+            let size_bytes = group[0].size;
+            let mut paths: Vec<String> = group
+                .iter()
+                .map(|candidate| candidate.path.to_string_lossy().to_string())
+                .collect();
+            paths.sort();
+            DuplicateGroup {
+                hash,
+                size_bytes,
+                reclaimable_bytes: size_bytes * (paths.len() as u64 - 1),
+                paths,
+            }
+        })
+        .collect();
+    groups.sort_by(|group_a, group_b| {
+        group_b
+            .reclaimable_bytes
+            .cmp(&group_a.reclaimable_bytes)
+            .then_with(|| group_a.paths.cmp(&group_b.paths))
+    });
+
+    DuplicateScan {
+        root: root.to_string_lossy().to_string(),
+        total_reclaimable_bytes: groups.iter().map(|group| group.reclaimable_bytes).sum(),
+        groups,
+        skipped_mounts: context.skipped_mounts(),
+        errors: context.scan_errors(),
+        omitted_error_count: context.omitted_error_count(),
+        cancelled,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::progress::NoopSink;
+    use crate::engine::test_support::write_contents;
+    use std::fs;
+
+    fn duplicates(root: &Path, min_size: u64) -> DuplicateScan {
+        find_duplicates(root, min_size, &NoopSink, &CancelToken::new())
+    }
+
+    #[test]
+    fn groups_identical_files_and_tells_near_copies_apart() {
+        let root = tempfile::tempdir().unwrap();
+        let dataset: Vec<u8> = (0..20_000_u32).map(|index| (index % 251) as u8).collect();
+        write_contents(&root.path().join("a/data.csv"), &dataset);
+        write_contents(&root.path().join("b/copy.csv"), &dataset);
+        write_contents(&root.path().join("c/data.csv"), &dataset);
+        // Same size, same first and last blocks, different middle.
+        let mut edited = dataset.clone();
+        edited[10_000] ^= 1;
+        write_contents(&root.path().join("d/edited.csv"), &edited);
+        write_contents(&root.path().join("e/small.txt"), b"tiny");
+        write_contents(&root.path().join("f/small.txt"), b"tiny");
+
+        let scan = duplicates(root.path(), 1024);
+        assert_eq!(scan.groups.len(), 1);
+        let group = &scan.groups[0];
+        let names: Vec<&str> = group
+            .paths
+            .iter()
+            .map(|path| path.strip_prefix(&scan.root).unwrap())
+            .collect();
+        assert_eq!(names, ["/a/data.csv", "/b/copy.csv", "/c/data.csv"]);
+        assert_eq!(group.size_bytes, 20_000);
+        assert_eq!(group.reclaimable_bytes, 40_000);
+        assert_eq!(scan.total_reclaimable_bytes, 40_000);
+        assert_eq!(group.hash, blake3::hash(&dataset).to_hex().to_string());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_are_not_duplicates() {
+        let root = tempfile::tempdir().unwrap();
+        write_contents(&root.path().join("a/image.iso"), &[7; 5000]);
+        fs::create_dir_all(root.path().join("b")).unwrap();
+        fs::hard_link(
+            root.path().join("a/image.iso"),
+            root.path().join("b/image.iso"),
+        )
+        .unwrap();
+        assert!(duplicates(root.path(), 1).groups.is_empty());
+    }
+
+    #[test]
+    fn a_cancelled_scan_reports_nothing() {
+        let root = tempfile::tempdir().unwrap();
+        write_contents(&root.path().join("a.bin"), &[1; 5000]);
+        write_contents(&root.path().join("b.bin"), &[1; 5000]);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let scan = find_duplicates(root.path(), 1, &NoopSink, &cancel);
+        assert!(scan.cancelled);
+        assert!(scan.groups.is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Upper bound on distinct error groups kept per scan, so a tree full of
+/// unreadable directories cannot grow the result without limit.
+pub const MAX_REPORTED_ERRORS: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    /// The entry vanished between listing and inspecting it.
+    NotFound,
+    Other,
+}
+
+impl From<&io::Error> for ScanErrorKind {
+    fn from(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            io::ErrorKind::NotFound => Self::NotFound,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Entries below `path` that could not be read. Errors for the same
+/// directory and kind are folded into one record.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ScanError {
+    pub path: String,
+    pub kind: ScanErrorKind,
+    pub entries_affected: u64,
+    /// The first OS error seen for this group, for display.
+    pub message: String,
+}
+
+/// Thread-safe collector the walk reports into.
+#[derive(Default)]
+pub struct ErrorLog {
+    groups: Mutex<HashMap<(PathBuf, ScanErrorKind), ScanError>>,
+    omitted: Mutex<u64>,
+}
+
+impl ErrorLog {
+    pub fn record(&self, path: &Path, error: &io::Error) {
+        let kind = ScanErrorKind::from(error);
+        let Ok(mut groups) = self.groups.lock() else {
+            return;
+        };
+
+        if let Some(group) = groups.get_mut(&(path.to_path_buf(), kind)) {
+            group.entries_affected += 1;
+            return;
+        }
+        if groups.len() >= MAX_REPORTED_ERRORS {
+            if let Ok(mut omitted) = self.omitted.lock() {
+                *omitted += 1;
+            }
+            return;
+        }
+
+        groups.insert(
+            (path.to_path_buf(), kind),
+            ScanError {
+                path: path.to_string_lossy().to_string(),
+                kind,
+                entries_affected: 1,
+                message: error.to_string(),
+            },
+        );
+    }
+
+    /// Recorded errors, most entries affected first.
+    pub fn errors(&self) -> Vec<ScanError> {
+        let mut errors: Vec<ScanError> = self
+            .groups
+            .lock()
+            .map(|groups| groups.values().cloned().collect())
+            .unwrap_or_default();
+        errors.sort_by(|error_a, error_b| {
+            error_b
+                .entries_affected
+                .cmp(&error_a.entries_affected)
+                .then_with(|| error_a.path.cmp(&error_b.path))
+        });
+        errors
+    }
+
+    /// Errors that were dropped after hitting `MAX_REPORTED_ERRORS`.
+    pub fn omitted(&self) -> u64 {
+        self.omitted.lock().map(|omitted| *omitted).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_by_path_and_kind() {
+        let log = ErrorLog::default();
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        let vanished = io::Error::from(io::ErrorKind::NotFound);
+
+        log.record(Path::new("/a"), &denied);
+        log.record(Path::new("/a"), &vanished);
+        log.record(Path::new("/a"), &vanished);
+        log.record(Path::new("/b"), &denied);
+
+        let errors = log.errors();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].path, "/a");
+        assert_eq!(errors[0].kind, ScanErrorKind::NotFound);
+        assert_eq!(errors[0].entries_affected, 2);
+    }
+
+    #[test]
+    fn caps_distinct_groups() {
+        let log = ErrorLog::default();
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        for index in 0..MAX_REPORTED_ERRORS + 3 {
+            log.record(&PathBuf::from(format!("/dir-{index}")), &denied);
+        }
+
+        assert_eq!(log.errors().len(), MAX_REPORTED_ERRORS);
+        assert_eq!(log.omitted(), 3);
+    }
+}
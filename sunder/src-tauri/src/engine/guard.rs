@@ -0,0 +1,372 @@
+use std::path::{Component, Path, PathBuf};
+
+/// How far a protection reaches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectionScope {
+    /// Only the path itself is off limits; things inside it may be purged.
+    Exact,
+    /// The path and everything below it are off limits.
+    Subtree,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ProtectedPath {
+    pub path: String,
+    pub scope: ProtectionScope,
+    pub reason: String,
+    /// False for entries the user added themselves.
+    pub builtin: bool,
+}
+
+/// System directories that must never be purged themselves.
+const SYSTEM_DIRS: &[&str] = &[
+    "/",
+    "/Applications",
+    "/Library",
+    "/System",
+    "/Users",
+    "/bin",
+    "/boot",
+    "/dev",
+    "/etc",
+    "/home",
+    "/lib",
+    "/lib64",
+    "/opt",
+    "/private",
+    "/proc",
+    "/root",
+    "/sbin",
+    "/sys",
+    "/usr",
+    "/var",
+];
+
+/// Paths under home, with why they are protected. Credential files sit
+/// inside cache folders on purpose: purging the whole folder would take
+/// them along, so only the regenerable parts can be cleaned.
+const HOME_SUBTREES: &[(&str, &str)] = &[
+    ("Desktop", "personal files"),
+    ("Documents", "personal files"),
+    ("Movies", "personal files"),
+    ("Music", "personal files"),
+    ("Pictures", "personal files"),
+    ("Videos", "personal files"),
+    (".ssh", "SSH keys"),
+    (".gnupg", "GPG keys"),
+    (".password-store", "password store"),
+    (".local/share/keyrings", "desktop keyrings"),
+    (".config", "application settings"),
+    (".aws", "cloud credentials"),
+    (".kube", "cluster credentials"),
+    (".cargo/credentials", "registry credentials"),
+    (".cargo/credentials.toml", "registry credentials"),
+    (".docker/config.json", "registry credentials"),
+    (".gradle/gradle.properties", "build credentials"),
+    (".m2/settings.xml", "build credentials"),
+];
+
+/// Lists the built-in protections for a given home directory.
+pub fn builtin_protected_paths(home: Option<&Path>) -> Vec<ProtectedPath> {
+    let mut protected: Vec<ProtectedPath> = SYSTEM_DIRS
+        .iter()
+        .map(|system_dir| ProtectedPath {
+            path: system_dir.to_string(),
+            scope: ProtectionScope::Exact,
+            reason: "system directory".into(),
+            builtin: true,
+        })
+        .collect();
+
+    if let Some(home) = home {
+        protected.push(ProtectedPath {
+            path: home.to_string_lossy().to_string(),
+            scope: ProtectionScope::Exact,
+            reason: "home directory".into(),
+            builtin: true,
+        });
+        protected.extend(
+            HOME_SUBTREES
+                .iter()
+                .map(|(relative_path, reason)| ProtectedPath {
+                    path: home.join(relative_path).to_string_lossy().to_string(),
+                    scope: ProtectionScope::Subtree,
+                    reason: reason.to_string(),
+                    builtin: true,
+                }),
+        );
+    }
+    protected
+}
+
+/// Resolves symlinks in the parent of `path` while leaving the last
+/// component alone, so a symlink target is judged by where the link lives.
+fn resolve_location(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => parent
+            .canonicalize()
+            .map(|canonical_parent| canonical_parent.join(file_name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn resolve_existing(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Decides whether a destructive operation may touch a path.
+pub struct PathGuard {
+    scan_root: Option<PathBuf>,
+    protected: Vec<(PathBuf, ProtectedPath)>,
+}
+
+impl PathGuard {
+    /// `scan_root` confines every operation to the tree the user scanned;
+    /// `None` refuses everything. `user_protected` entries protect their
+    /// whole subtree.
+    pub fn new(home: Option<&Path>, scan_root: Option<&Path>, user_protected: &[String]) -> Self {
+        let mut protected = builtin_protected_paths(home);
+        protected.extend(user_protected.iter().map(|user_path| ProtectedPath {
+            path: user_path.clone(),
+            scope: ProtectionScope::Subtree,
+            reason: "on your protected list".into(),
+            builtin: false,
+        }));
+
+        Self {
+            scan_root: scan_root.map(resolve_existing),
+            protected: protected
+                .into_iter()
+                .map(|entry| (resolve_existing(Path::new(&entry.path)), entry))
+                .collect(),
+        }
+    }
+
+    /// Returns the reason `path` must not be purged, if there is one.
+    pub fn check(&self, path: &Path) -> Result<(), String> {
+        self.check_in_place(path)?;
+        let location = resolve_location(path);
+        for (protected_path, entry) in &self.protected {
+            if protected_path.starts_with(&location) && *protected_path != location {
+                return Err(format!(
+                    "{} contains {}, which is protected ({})",
+                    path.display(),
+                    entry.path,
+                    entry.reason
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `check`, for operations that shrink a folder in place, such as
+    /// a package manager's own cleanup: the folder may contain protected
+    /// paths, since the operation leaves them alone.
+    pub fn check_in_place(&self, path: &Path) -> Result<(), String> {
+        let shown = path.display();
+        if !path.is_absolute() {
+            return Err(format!("{shown} is not an absolute path"));
+        }
+        // `..` would be judged as written but resolved by the trash, so
+        // `~/.cargo/..` would pass as a folder inside home and trash home.
+        let is_relative_step =
+            |component: Component| matches!(component, Component::CurDir | Component::ParentDir);
+        if path.components().any(is_relative_step) {
+            return Err(format!("{shown} contains . or .. components"));
+        }
+
+        let Some(scan_root) = &self.scan_root else {
+            return Err(format!("{shown} cannot be purged before a scan has run"));
+        };
+        let location = resolve_location(path);
+        if location == *scan_root {
+            return Err(format!("{shown} is the scanned root itself"));
+        }
+        if !location.starts_with(scan_root) {
+            return Err(format!(
+                "{shown} is outside the scanned root {}",
+                scan_root.display()
+            ));
+        }
+
+        let is_symlink =
+            std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink());
+        if is_symlink {
+            let link_target = resolve_existing(path);
+            if !link_target.starts_with(scan_root) {
+                return Err(format!(
+                    "{shown} is a symlink to {}, outside the scanned root",
+                    link_target.display()
+                ));
+            }
+        }
+
+        for (protected_path, entry) in &self.protected {
+            let matches = match entry.scope {
+                ProtectionScope::Exact => location == *protected_path,
+                ProtectionScope::Subtree => location.starts_with(protected_path),
+            };
+            if matches {
+                return Err(format!("{shown} is protected ({})", entry.reason));
+            }
+        }
+        Ok(())
+    }
+}
+
+// -- User list persistence --
+
+/// Reads the user's protected paths. A missing file is an empty list.
+pub fn load_user_protected(list_path: &Path) -> Result<Vec<String>, String> {
+    match std::fs::read_to_string(list_path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|parse_error| format!("Invalid protected path list: {parse_error}")),
+        Err(read_error) if read_error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(read_error) => Err(format!("Cannot read protected path list: {read_error}")),
+    }
+}
+
+pub fn save_user_protected(list_path: &Path, user_protected: &[String]) -> Result<(), String> {
+    if let Some(parent) = list_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|create_error| {
+            format!("Cannot create {}: {create_error}", parent.display())
+        })?;
+    }
+    let contents = serde_json::to_string_pretty(user_protected)
+        .map_err(|encode_error| encode_error.to_string())?;
+    std::fs::write(list_path, contents)
+        .map_err(|write_error| format!("Cannot save protected path list: {write_error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn guarded_home() -> (tempfile::TempDir, PathBuf) {
+        let home = tempfile::tempdir().unwrap();
+        let home_path = home.path().canonicalize().unwrap();
+        for relative_path in [".ssh", "Documents/taxes", ".cache/pip", ".cargo/registry"] {
+            fs::create_dir_all(home_path.join(relative_path)).unwrap();
+        }
+        fs::write(home_path.join(".cargo/credentials.toml"), "token").unwrap();
+        (home, home_path)
+    }
+
+    #[test]
+    fn allows_regenerable_folders_inside_the_root() {
+        let (_home, home_path) = guarded_home();
+        let guard = PathGuard::new(Some(&home_path), Some(&home_path), &[]);
+
+        assert!(guard.check(&home_path.join(".cache/pip")).is_ok());
+        assert!(guard.check(&home_path.join(".cargo/registry")).is_ok());
+    }
+
+    #[test]
+    fn refuses_builtin_protected_paths() {
+        let (_home, home_path) = guarded_home();
+        let guard = PathGuard::new(Some(&home_path), Some(&home_path), &[]);
+
+        let ssh_refusal = guard.check(&home_path.join(".ssh")).unwrap_err();
+        assert!(ssh_refusal.contains("SSH keys"));
+        assert!(guard.check(&home_path.join("Documents/taxes")).is_err());
+        assert!(guard
+            .check(&home_path)
+            .unwrap_err()
+            .contains("scanned root"));
+
+        let cargo_refusal = guard.check(&home_path.join(".cargo")).unwrap_err();
+        assert!(cargo_refusal.contains("contains"));
+    }
+
+    #[test]
+    fn in_place_checks_allow_folders_holding_protected_paths() {
+        let (_home, home_path) = guarded_home();
+        let guard = PathGuard::new(Some(&home_path), Some(&home_path.join(".cargo")), &[]);
+
+        assert!(guard
+            .check_in_place(&home_path.join(".cargo/registry"))
+            .is_ok());
+        assert!(guard
+            .check_in_place(&home_path.join(".cargo/credentials.toml"))
+            .is_err());
+        assert!(guard.check_in_place(&home_path.join(".ssh")).is_err());
+    }
+
+    #[test]
+    fn refuses_parent_and_current_dir_components() {
+        let (_home, home_path) = guarded_home();
+        let guard = PathGuard::new(Some(&home_path), Some(&home_path), &[]);
+
+        let above_home = guard.check(&home_path.join("..")).unwrap_err();
+        assert!(above_home.contains(".. components"));
+        assert!(guard.check(&home_path.join(".cargo/..")).is_err());
+        assert!(guard.check_in_place(&home_path.join(".cargo/..")).is_err());
+        assert!(guard.check(&home_path.join(".cache/pip/../pip")).is_err());
+    }
+
+    #[test]
+    fn refuses_system_directories() {
+        let guard = PathGuard::new(None, Some(Path::new("/")), &[]);
+        assert!(guard
+            .check(Path::new("/usr"))
+            .unwrap_err()
+            .contains("system directory"));
+        assert!(guard.check(Path::new("/var")).is_err());
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_scanned_root() {
+        let (_home, home_path) = guarded_home();
+        let guard = PathGuard::new(Some(&home_path), Some(&home_path.join(".cache")), &[]);
+
+        let refusal = guard.check(&home_path.join(".cargo/registry")).unwrap_err();
+        assert!(refusal.contains("outside the scanned root"));
+        assert!(PathGuard::new(None, None, &[])
+            .check(&home_path.join(".cache/pip"))
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_symlinks_that_escape_the_root() {
+        let (_home, home_path) = guarded_home();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), home_path.join(".cache/escape")).unwrap();
+        let guard = PathGuard::new(Some(&home_path), Some(&home_path.join(".cache")), &[]);
+
+        let link_refusal = guard.check(&home_path.join(".cache/escape")).unwrap_err();
+        assert!(link_refusal.contains("symlink"));
+
+        let through_link = guard
+            .check(&home_path.join(".cache/escape/data"))
+            .unwrap_err();
+        assert!(through_link.contains("outside the scanned root"));
+    }
+
+    #[test]
+    fn user_entries_protect_their_subtree() {
+        let (_home, home_path) = guarded_home();
+        let pip = home_path.join(".cache/pip");
+        let guard = PathGuard::new(
+            Some(&home_path),
+            Some(&home_path),
+            &[pip.to_string_lossy().to_string()],
+        );
+
+        assert!(guard.check(&pip).unwrap_err().contains("protected list"));
+        assert!(guard.check(&home_path.join(".cache")).is_err());
+    }
+
+    #[test]
+    fn user_list_round_trips() {
+        let config = tempfile::tempdir().unwrap();
+        let list_path = config.path().join("nested/protected_paths.json");
+
+        assert!(load_user_protected(&list_path).unwrap().is_empty());
+        save_user_protected(&list_path, &["/data/keep".to_string()]).unwrap();
+        assert_eq!(load_user_protected(&list_path).unwrap(), vec!["/data/keep"]);
+    }
+}
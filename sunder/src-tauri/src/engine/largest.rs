@@ -0,0 +1,217 @@
+use rayon::prelude::*;
+use std::path::{Component, Path};
+
+use super::activity::epoch_seconds;
+use super::cancel::CancelToken;
+use super::crawler::{walk_dir, WalkContext};
+use super::errors::ScanError;
+use super::mounts::SkippedMount;
+use super::progress::{FolderProgress, ProgressSink};
+
+/// Keeps a careless `limit` from holding the whole tree in memory.
+const MAX_LIMIT: usize = 10_000;
+
+// -- Shared types --
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct LargestFilesOptions {
+    /// How many files to return.
+    pub limit: usize,
+    /// See `ScanOptions::same_filesystem`.
+    pub same_filesystem: bool,
+}
+
+impl Default for LargestFilesOptions {
+    fn default() -> Self {
+        Self {
+            limit: 100,
+            same_filesystem: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LargeFile {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Seconds since the Unix epoch, when the filesystem reports it.
+    pub modified: Option<u64>,
+    /// The folder directly under the root that holds the file, matching a
+    /// `CategorizedFolder` path. `None` for files directly in the root.
+    pub top_level_folder: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LargestFilesScan {
+    pub root: String,
+    /// Largest first.
+    pub files: Vec<LargeFile>,
+    pub skipped_mounts: Vec<SkippedMount>,
+    pub errors: Vec<ScanError>,
+    pub omitted_error_count: u64,
+    pub cancelled: bool,
+}
+
+// -- Scan --
+
+fn top_level_folder(root: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(root).ok()?;
+    let mut components = relative.components();
+    let first = components.next()?;
+    // A file directly in the root has nothing after its own name.
+    components.next()?;
+    match first {
+        Component::Normal(name) => Some(root.join(name).to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+/// Walks everything below `root` and returns its largest regular files.
+/// Only the current top `limit` files are held at any time.
+pub fn find_largest_files(
+    root: &Path,
+    options: &LargestFilesOptions,
+    sink: &dyn ProgressSink,
+    cancel: &CancelToken,
+) -> Result<LargestFilesScan, String> {
+    let mut context = WalkContext::new(cancel).tracking_largest_files(options.limit.min(MAX_LIMIT));
+    if options.same_filesystem {
+        context = context.same_filesystem_as(root);
+    }
+
+    let mut child_dirs = Vec::new();
+    for dir_entry in std::fs::read_dir(root)
+        .map_err(|read_error| read_error.to_string())?
+        .filter_map(|entry_result| {
+            entry_result
+                .map_err(|entry_error| context.record_error(root, &entry_error))
+                .ok()
+        })
+    {
+        match dir_entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => child_dirs.push(dir_entry.path()),
+            Ok(file_type) if file_type.is_file() => match dir_entry.metadata() {
+                Ok(metadata) => context.offer_file(&dir_entry, &metadata),
+                Err(metadata_error) => context.record_error(root, &metadata_error),
+            },
+            Ok(_) => {}
+            Err(file_type_error) => context.record_error(root, &file_type_error),
+        }
+    }
+
+    let progress = FolderProgress::start(sink, child_dirs.len() as u64);
+    child_dirs.par_iter().for_each(|child_path| {
+        if cancel.is_cancelled() {
+            return;
+        }
+        let name = child_path
+            .file_name()
+            .map(|os_name| os_name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !context.crosses_mount(child_path) {
+            walk_dir(child_path, &context);
+        }
+        progress.folder_done(&name);
+    });
+
+    let cancelled = cancel.is_cancelled();
+    if !cancelled {
+        progress.finish();
+    }
+
+    let files = context
+        .largest_files()
+        .into_iter()
+        .map(|tracked| LargeFile {
+            top_level_folder: top_level_folder(root, &tracked.path),
+            path: tracked.path.to_string_lossy().to_string(),
+            size_bytes: tracked.apparent_bytes,
+            modified: tracked.modified.map(epoch_seconds),
+        })
+        .collect();
+
+    Ok(LargestFilesScan {
+        root: root.to_string_lossy().to_string(),
+        files,
+        skipped_mounts: context.skipped_mounts(),
+        errors: context.scan_errors(),
+        omitted_error_count: context.omitted_error_count(),
+        cancelled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::progress::NoopSink;
+    use crate::engine::test_support::write_file;
+    use std::fs;
+
+    fn largest(root: &Path, limit: usize) -> LargestFilesScan {
+        let options = LargestFilesOptions {
+            limit,
+            ..LargestFilesOptions::default()
+        };
+        find_largest_files(root, &options, &NoopSink, &CancelToken::new()).unwrap()
+    }
+
+    #[test]
+    fn keeps_only_the_largest_files_in_order() {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("Downloads/ubuntu.iso"), 5000);
+        write_file(&root.path().join("Videos/trip/day1.mp4"), 3000);
+        write_file(&root.path().join("core.1234"), 4000);
+        for index in 0..50 {
+            write_file(&root.path().join(format!("src/file{index}.rs")), 10 + index);
+        }
+
+        let scan = largest(root.path(), 3);
+        let found: Vec<(&str, u64)> = scan
+            .files
+            .iter()
+            .map(|file| (file.path.rsplit('/').next().unwrap(), file.size_bytes))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("ubuntu.iso", 5000),
+                ("core.1234", 4000),
+                ("day1.mp4", 3000)
+            ]
+        );
+
+        let videos = root.path().join("Videos").to_string_lossy().to_string();
+        assert_eq!(scan.files[2].top_level_folder, Some(videos));
+        assert_eq!(scan.files[1].top_level_folder, None);
+        assert!(scan.files[0].modified.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_are_listed_once() {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("a/disk.raw"), 900);
+        fs::create_dir_all(root.path().join("b")).unwrap();
+        fs::hard_link(
+            root.path().join("a/disk.raw"),
+            root.path().join("b/disk.raw"),
+        )
+        .unwrap();
+        write_file(&root.path().join("c/small.bin"), 10);
+
+        let sizes: Vec<u64> = largest(root.path(), 5)
+            .files
+            .iter()
+            .map(|file| file.size_bytes)
+            .collect();
+        assert_eq!(sizes, [900, 10]);
+    }
+
+    #[test]
+    fn a_zero_limit_finds_nothing() {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("big.bin"), 100);
+        assert!(largest(root.path(), 0).files.is_empty());
+    }
+}
@@ -0,0 +1,30 @@
+//! The scan engine: everything that touches the filesystem lives here and
+//! knows nothing about Tauri. Commands drive it and forward progress through
+//! a [`progress::ProgressSink`].
+
+pub mod activity;
+pub mod artifacts;
+pub mod audit;
+pub mod cancel;
+pub mod category;
+pub mod classify;
+pub mod cleanup;
+pub mod containers;
+pub mod crawler;
+pub mod dedupe;
+pub mod devpurge;
+pub mod duplicates;
+pub mod errors;
+pub mod guard;
+pub mod largest;
+pub mod mounts;
+pub mod progress;
+pub mod purge;
+pub mod restore;
+pub mod rules;
+pub mod scan;
+pub mod snapshot;
+#[cfg(test)]
+pub mod test_support;
+pub mod tree;
+pub mod xdg;
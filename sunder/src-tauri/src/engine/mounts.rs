@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+/// A mount point the scan stepped around instead of crossing into.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SkippedMount {
+    pub path: String,
+    pub fs_type: String,
+}
+
+/// Mount points and their filesystem types, read from the kernel's mount
+/// table. Only used to label skipped mounts, so a missing table just means
+/// every mount is reported as "unknown".
+#[derive(Debug, Default)]
+pub struct MountTable {
+    mounts: Vec<(PathBuf, String)>,
+}
+
+impl MountTable {
+    #[cfg(target_os = "linux")]
+    pub fn load() -> Self {
+        std::fs::read_to_string("/proc/self/mountinfo")
+            .map(|mountinfo| Self::parse_mountinfo(&mountinfo))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn load() -> Self {
+        Self::default()
+    }
+
+    /// Parses the `/proc/self/mountinfo` format: the mount point is the fifth
+    /// field and the filesystem type is the first field after the lone `-`
+    /// separator that ends the optional fields.
+    pub fn parse_mountinfo(mountinfo: &str) -> Self {
+        let mounts = mountinfo
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(' ').collect();
+                let mount_point = fields.get(4)?;
+                let separator = fields.iter().position(|field| *field == "-")?;
+                let fs_type = fields.get(separator + 1)?;
+                Some((
+                    PathBuf::from(unescape_mount_path(mount_point)),
+                    fs_type.to_string(),
+                ))
+            })
+            .collect();
+        Self { mounts }
+    }
+
+    /// The filesystem type of the mount that contains `path`.
+    pub fn fs_type(&self, path: &Path) -> String {
+        self.mounts
+            .iter()
+            .filter(|(mount_point, _)| path.starts_with(mount_point))
+            .max_by_key(|(mount_point, _)| mount_point.components().count())
+            .map(|(_, fs_type)| fs_type.clone())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+// The kernel escapes space, tab, newline and backslash in mount paths as
+// three-digit octal sequences such as `\040`.
+fn unescape_mount_path(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'\\' && index + 3 < bytes.len() {
+            let octal = std::str::from_utf8(&bytes[index + 1..index + 4]).unwrap_or("");
+            if let Ok(value) = u8::from_str_radix(octal, 8) {
+                unescaped.push(value);
+                index += 4;
+                continue;
+            }
+        }
+        unescaped.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&unescaped).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_MOUNTINFO: &str = "\
+22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw
+30 22 0:26 / /home/dev/nas rw,nosuid shared:12 master:3 - nfs4 server:/export rw
+31 22 0:27 / /home/dev/My\\040Drive rw - fuse.rclone drive: rw
+32 30 0:28 / /home/dev/nas/scratch rw - tmpfs tmpfs rw";
+
+    #[test]
+    fn resolves_the_innermost_mount() {
+        let table = MountTable::parse_mountinfo(SAMPLE_MOUNTINFO);
+
+        assert_eq!(table.fs_type(Path::new("/home/dev/projects")), "ext4");
+        assert_eq!(table.fs_type(Path::new("/home/dev/nas")), "nfs4");
+        assert_eq!(table.fs_type(Path::new("/home/dev/nas/scratch/x")), "tmpfs");
+    }
+
+    #[test]
+    fn unescapes_mount_paths() {
+        let table = MountTable::parse_mountinfo(SAMPLE_MOUNTINFO);
+        assert_eq!(
+            table.fs_type(Path::new("/home/dev/My Drive")),
+            "fuse.rclone"
+        );
+    }
+
+    #[test]
+    fn unknown_without_a_table() {
+        assert_eq!(MountTable::default().fs_type(Path::new("/mnt")), "unknown");
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ScanProgress {
+    pub scanned_folders: u64,
+    pub total_folders: u64,
+    pub percent: f64,
+    pub current_folder: String,
+}
+
+/// Receives progress updates from the engine. Scans call it from rayon
+/// worker threads, hence the `Send + Sync` bound.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, progress: ScanProgress);
+}
+
+/// Drops every update. Handy for tests and headless callers.
+pub struct NoopSink;
+
+impl ProgressSink for NoopSink {
+    fn report(&self, _progress: ScanProgress) {}
+}
+
+/// Forwards updates over an mpsc channel. A closed receiver is not an error;
+/// the scan simply keeps going without anyone listening.
+pub struct ChannelSink {
+    sender: Sender<ScanProgress>,
+}
+
+impl ChannelSink {
+    pub fn new(sender: Sender<ScanProgress>) -> Self {
+        Self { sender }
+    }
+}
+
+impl ProgressSink for ChannelSink {
+    fn report(&self, progress: ScanProgress) {
+        let _ = self.sender.send(progress);
+    }
+}
+
+/// Counts finished folders and reports each step to a sink. Folders finish
+/// in whatever order the workers get to them, so the counter and the report
+/// share one lock to keep `scanned_folders` monotonic.
+pub struct FolderProgress<'a> {
+    sink: &'a dyn ProgressSink,
+    total_folders: u64,
+    scanned_folders: Mutex<u64>,
+}
+
+impl<'a> FolderProgress<'a> {
+    /// Reports the empty starting state straight away so listeners learn the
+    /// total before the first folder finishes.
+    pub fn start(sink: &'a dyn ProgressSink, total_folders: u64) -> Self {
+        sink.report(ScanProgress {
+            scanned_folders: 0,
+            total_folders,
+            percent: 0.0,
+            current_folder: String::new(),
+        });
+        Self {
+            sink,
+            total_folders,
+            scanned_folders: Mutex::new(0),
+        }
+    }
+
+    pub fn folder_done(&self, folder_name: &str) {
+        self.folders_done(1, folder_name);
+    }
+
+    /// Counts several folders at once, reporting a single step.
+    pub fn folders_done(&self, count: u64, folder_name: &str) {
+        if let Ok(mut scanned_count) = self.scanned_folders.lock() {
+            *scanned_count += count;
+            self.sink.report(ScanProgress {
+                scanned_folders: *scanned_count,
+                total_folders: self.total_folders,
+                percent: (*scanned_count as f64 / self.total_folders as f64) * 100.0,
+                current_folder: folder_name.to_string(),
+            });
+        }
+    }
+
+    pub fn finish(&self) {
+        self.sink.report(ScanProgress {
+            scanned_folders: self.total_folders,
+            total_folders: self.total_folders,
+            percent: 100.0,
+            current_folder: String::new(),
+        });
+    }
+}
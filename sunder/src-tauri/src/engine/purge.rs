@@ -0,0 +1,669 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+use super::audit::{AuditEntry, AuditLog};
+use super::cancel::CancelToken;
+use super::category::Category;
+use super::crawler::{walk_dir, WalkContext};
+use super::guard::PathGuard;
+use super::rules::{RuleSet, SafetyLevel};
+
+// -- Shared types --
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PurgeTarget {
+    pub path: String,
+    pub size_bytes: u64,
+    pub category: Category,
+    /// Things the user should know before confirming. Warnings never block
+    /// a plan; refusals are errors instead.
+    pub warnings: Vec<String>,
+}
+
+/// A requested path the guard would not let into the plan.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RefusedTarget {
+    pub path: String,
+    pub reason: String,
+}
+
+/// The dry-run half of a purge: exactly what would be moved to the trash.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PurgePlan {
+    pub plan_id: String,
+    pub created_at: u64,
+    pub targets: Vec<PurgeTarget>,
+    pub total_bytes: u64,
+    /// Requested paths left out of the plan, each with the reason.
+    pub refused: Vec<RefusedTarget>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PurgeOutcome {
+    Trashed,
+    Failed,
+    /// Replaced by a link to an identical file rather than removed.
+    Linked,
+    /// Replaced by a link, then put back when a later step of the same
+    /// dedupe failed.
+    RolledBack,
+    /// Shrunk in place by the ecosystem's own cleanup tool.
+    Cleaned,
+    /// Deleted by a container engine; there is nothing to restore.
+    Removed,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PurgeItemResult {
+    pub path: String,
+    pub size_bytes: u64,
+    pub category: Category,
+    pub outcome: PurgeOutcome,
+    pub error: Option<String>,
+    pub trash_location: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PurgeReport {
+    pub plan_id: String,
+    pub items: Vec<PurgeItemResult>,
+    pub trashed_bytes: u64,
+}
+
+/// A plan together with what each target looked like when it was planned.
+pub struct StoredPlan {
+    pub plan: PurgePlan,
+    fingerprints: Vec<Fingerprint>,
+}
+
+/// Moves a path out of the way and returns where it went, if known. The
+/// real implementation is the system trash; tests substitute their own.
+pub trait Trasher: Send + Sync {
+    fn trash(&self, path: &Path) -> Result<Option<PathBuf>, String>;
+}
+
+/// The freedesktop trash on Linux, the Recycle Bin on Windows and the
+/// Finder trash on macOS, via the `trash` crate.
+pub struct SystemTrash;
+
+impl Trasher for SystemTrash {
+    fn trash(&self, path: &Path) -> Result<Option<PathBuf>, String> {
+        trash::delete(path).map_err(|trash_error| trash_error.to_string())?;
+        Ok(locate_in_trash(path))
+    }
+}
+
+/// Finds the newest freedesktop trash entry for `original` and returns the
+/// path of the trashed file, next to its `info/*.trashinfo` record.
+#[cfg(all(
+    unix,
+    not(target_os = "macos"),
+    not(target_os = "ios"),
+    not(target_os = "android")
+))]
+fn locate_in_trash(original: &Path) -> Option<PathBuf> {
+    let items = trash::os_limited::list().ok()?;
+    let newest = items
+        .into_iter()
+        .filter(|item| item.original_path() == original)
+        .max_by_key(|item| item.time_deleted)?;
+
+    let info_file = PathBuf::from(newest.id);
+    let trashed_name = info_file.file_stem()?;
+    let trash_dir = info_file.parent()?.parent()?;
+    Some(trash_dir.join("files").join(trashed_name))
+}
+
+#[cfg(not(all(
+    unix,
+    not(target_os = "macos"),
+    not(target_os = "ios"),
+    not(target_os = "android")
+)))]
+fn locate_in_trash(_original: &Path) -> Option<PathBuf> {
+    None
+}
+
+// -- Fingerprints --
+
+/// Cheap summary of a tree used to notice that a target changed between
+/// planning and executing. Any file added, removed, resized or modified
+/// moves at least one of these fields.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Fingerprint {
+    total_len: u64,
+    entry_count: u64,
+    newest_mtime: Option<SystemTime>,
+}
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    let mut summary = Fingerprint::default();
+    // A symlinked target is trashed as a link, so only the link itself
+    // is fingerprinted.
+    let walker = WalkDir::new(path).follow_root_links(false);
+
+    for entry in walker
+        .into_iter()
+        .filter_map(|entry_result| entry_result.ok())
+    {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        summary.entry_count += 1;
+        if metadata.is_file() {
+            summary.total_len += metadata.len();
+        }
+        if let Ok(modified) = metadata.modified() {
+            summary.newest_mtime = summary.newest_mtime.max(Some(modified));
+        }
+    }
+    summary
+}
+
+// -- Planning --
+
+pub(super) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+pub(super) fn next_plan_id() -> String {
+    static PLAN_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    format!(
+        "plan-{nanos:x}-{}",
+        PLAN_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Sizes, classifies and fingerprints every path without touching any of
+/// them. Paths the guard refuses are listed in `refused` rather than
+/// planned. Fails if a path is missing or is nested inside another target.
+pub fn plan_purge(
+    paths: &[String],
+    guard: &PathGuard,
+    rules: &RuleSet,
+    cancel: &CancelToken,
+) -> Result<StoredPlan, String> {
+    if paths.is_empty() {
+        return Err("Nothing selected to purge".into());
+    }
+
+    let mut target_paths: Vec<PathBuf> = Vec::new();
+    let mut refused = Vec::new();
+    let mut seen_paths = HashSet::new();
+    for raw_path in paths {
+        let target_path = PathBuf::from(raw_path);
+        if std::fs::symlink_metadata(&target_path).is_err() {
+            return Err(format!("{raw_path} does not exist"));
+        }
+        if !seen_paths.insert(target_path.clone()) {
+            continue;
+        }
+        match guard.check(&target_path) {
+            Ok(()) => target_paths.push(target_path),
+            Err(reason) => refused.push(RefusedTarget {
+                path: raw_path.clone(),
+                reason,
+            }),
+        }
+    }
+
+    for target_path in &target_paths {
+        if let Some(ancestor) = target_paths
+            .iter()
+            .find(|other| *other != target_path && target_path.starts_with(other))
+        {
+            return Err(format!(
+                "{} is inside {}, which is already part of this purge",
+                target_path.display(),
+                ancestor.display()
+            ));
+        }
+    }
+
+    let context = WalkContext::new(cancel);
+    let mut targets = Vec::new();
+    let mut fingerprints = Vec::new();
+
+    for target_path in &target_paths {
+        let metadata = std::fs::symlink_metadata(target_path)
+            .map_err(|metadata_error| format!("{}: {metadata_error}", target_path.display()))?;
+        let mut warnings = Vec::new();
+
+        let size_bytes = if metadata.is_dir() {
+            let node = walk_dir(target_path, &context);
+            if node.shared_bytes > 0 {
+                warnings.push(format!(
+                    "{} bytes are hard-linked elsewhere and will not be freed",
+                    node.shared_bytes
+                ));
+            }
+            node.apparent_bytes
+        } else {
+            if metadata.file_type().is_symlink() {
+                warnings.push("This is a symlink; only the link will be moved".into());
+            }
+            metadata.len()
+        };
+
+        let classification = rules.classify(target_path);
+        match classification.safety {
+            SafetyLevel::Never => warnings.push(format!(
+                "Marked as never safe to delete: {}",
+                classification.description
+            )),
+            // The fallback is also `Caution`, but says nothing worth warning about.
+            SafetyLevel::Caution if classification.rule_id.is_some() => {
+                warnings.push(classification.description)
+            }
+            _ => {}
+        }
+
+        targets.push(PurgeTarget {
+            path: target_path.to_string_lossy().to_string(),
+            size_bytes,
+            category: classification.category,
+            warnings,
+        });
+        fingerprints.push(fingerprint(target_path));
+    }
+
+    if cancel.is_cancelled() {
+        return Err("Purge planning was cancelled".into());
+    }
+
+    for scan_error in context.scan_errors() {
+        if let Some(target) = targets
+            .iter_mut()
+            .find(|target| Path::new(&scan_error.path).starts_with(&target.path))
+        {
+            target.warnings.push(format!(
+                "{} could not be read, so the size is a lower bound",
+                scan_error.path
+            ));
+        }
+    }
+
+    let total_bytes = targets.iter().map(|target| target.size_bytes).sum();
+    Ok(StoredPlan {
+        plan: PurgePlan {
+            plan_id: next_plan_id(),
+            created_at: unix_now(),
+            targets,
+            total_bytes,
+            refused,
+        },
+        fingerprints,
+    })
+}
+
+// -- Executing --
+
+/// Moves every target of `stored` to the trash, recording each one in the
+/// audit log as it goes. Nothing is touched unless the log is writable and
+/// every target still passes the guard and matches its fingerprint.
+pub fn execute_purge(
+    stored: &StoredPlan,
+    guard: &PathGuard,
+    trasher: &dyn Trasher,
+    audit: &AuditLog,
+) -> Result<PurgeReport, String> {
+    for target in &stored.plan.targets {
+        guard
+            .check(Path::new(&target.path))
+            .map_err(|reason| format!("Refusing to purge: {reason}"))?;
+    }
+
+    let changed: Vec<&str> = stored
+        .plan
+        .targets
+        .iter()
+        .zip(&stored.fingerprints)
+        .filter(|(target, planned)| fingerprint(Path::new(&target.path)) != **planned)
+        .map(|(target, _)| target.path.as_str())
+        .collect();
+    if !changed.is_empty() {
+        return Err(format!(
+            "Refusing to purge: {} changed since the plan was made. Review a new plan first.",
+            changed.join(", ")
+        ));
+    }
+
+    let mut audit_writer = audit.writer()?;
+    let mut audit_failure: Option<String> = None;
+    let mut items = Vec::new();
+
+    for (index, target) in stored.plan.targets.iter().enumerate() {
+        // Once an entry cannot be recorded, nothing else is removed.
+        let trash_result = match &audit_failure {
+            Some(audit_error) => Err(format!("Skipped: {audit_error}")),
+            None => trasher.trash(Path::new(&target.path)),
+        };
+        let item = match trash_result {
+            Ok(location) => PurgeItemResult {
+                path: target.path.clone(),
+                size_bytes: target.size_bytes,
+                category: target.category.clone(),
+                outcome: PurgeOutcome::Trashed,
+                error: None,
+                trash_location: location.map(|location| location.to_string_lossy().to_string()),
+            },
+            Err(trash_error) => PurgeItemResult {
+                path: target.path.clone(),
+                size_bytes: target.size_bytes,
+                category: target.category.clone(),
+                outcome: PurgeOutcome::Failed,
+                error: Some(trash_error),
+                trash_location: None,
+            },
+        };
+
+        if audit_failure.is_none() {
+            let entry = AuditEntry::new(&stored.plan.plan_id, index, unix_now(), &item);
+            if let Err(audit_error) = audit_writer.append(&entry) {
+                audit_failure = Some(audit_error);
+            }
+        }
+        items.push(item);
+    }
+
+    let trashed_bytes = items
+        .iter()
+        .filter(|item| item.outcome == PurgeOutcome::Trashed)
+        .map(|item| item.size_bytes)
+        .sum();
+
+    Ok(PurgeReport {
+        plan_id: stored.plan.plan_id.clone(),
+        items,
+        trashed_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_support::{open_guard, scratch_audit, write_file};
+    use std::fs;
+    use std::sync::Mutex;
+
+    /// Moves targets into a scratch directory instead of the real trash.
+    struct ScratchTrash {
+        bin: PathBuf,
+        trashed: Mutex<Vec<PathBuf>>,
+    }
+
+    impl Trasher for ScratchTrash {
+        fn trash(&self, path: &Path) -> Result<Option<PathBuf>, String> {
+            let destination = self.bin.join(path.file_name().unwrap());
+            fs::rename(path, &destination).map_err(|rename_error| rename_error.to_string())?;
+            self.trashed.lock().unwrap().push(path.to_path_buf());
+            Ok(Some(destination))
+        }
+    }
+
+    fn path_string(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    fn scratch_trash() -> (tempfile::TempDir, ScratchTrash) {
+        let bin = tempfile::tempdir().unwrap();
+        let trasher = ScratchTrash {
+            bin: bin.path().to_path_buf(),
+            trashed: Mutex::new(Vec::new()),
+        };
+        (bin, trasher)
+    }
+
+    #[test]
+    fn plan_lists_size_and_category_without_touching_anything() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join(".npm/_cacache/blob"), 900);
+        write_file(&home.path().join("Downloads/big.iso"), 100);
+
+        let stored = plan_purge(
+            &[
+                path_string(&home.path().join(".npm")),
+                path_string(&home.path().join("Downloads")),
+            ],
+            &open_guard(home.path()),
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(stored.plan.total_bytes, 1000);
+        assert_eq!(stored.plan.targets[0].category, Category::PackageCaches);
+        assert!(stored.plan.targets[0].warnings.is_empty());
+        assert_eq!(stored.plan.targets[1].category, Category::UserFiles);
+        assert!(!stored.plan.targets[1].warnings.is_empty());
+        assert!(home.path().join(".npm/_cacache/blob").exists());
+    }
+
+    #[test]
+    fn plan_rejects_missing_and_nested_paths() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join("cache/inner/blob"), 1);
+        let cancel = CancelToken::new();
+        let guard = open_guard(home.path());
+        let rules = RuleSet::builtin();
+
+        assert!(plan_purge(&[], &guard, &rules, &cancel).is_err());
+        assert!(plan_purge(
+            &[path_string(&home.path().join("nope"))],
+            &guard,
+            &rules,
+            &cancel
+        )
+        .is_err());
+
+        let nested_error = plan_purge(
+            &[
+                path_string(&home.path().join("cache")),
+                path_string(&home.path().join("cache/inner")),
+            ],
+            &guard,
+            &rules,
+            &cancel,
+        )
+        .err()
+        .unwrap();
+        assert!(nested_error.contains("inside"));
+    }
+
+    #[test]
+    fn execute_trashes_every_planned_target() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join("target/debug/app"), 300);
+        write_file(&home.path().join("stray.log"), 20);
+        let (_bin, trasher) = scratch_trash();
+        let (_data_dir, audit) = scratch_audit();
+
+        let stored = plan_purge(
+            &[
+                path_string(&home.path().join("target")),
+                path_string(&home.path().join("stray.log")),
+            ],
+            &open_guard(home.path()),
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+        let report = execute_purge(&stored, &open_guard(home.path()), &trasher, &audit).unwrap();
+
+        assert_eq!(report.trashed_bytes, 320);
+        assert!(report
+            .items
+            .iter()
+            .all(|item| item.outcome == PurgeOutcome::Trashed));
+        assert!(!home.path().join("target").exists());
+        assert_eq!(trasher.trashed.lock().unwrap().len(), 2);
+
+        let logged = audit.entries().unwrap();
+        assert_eq!(logged.len(), 2);
+        assert_eq!(logged[0].plan_id, stored.plan.plan_id);
+        assert_eq!(logged[0].path, report.items[0].path);
+        assert_eq!(logged[0].size_bytes, 300);
+        assert!(logged[0]
+            .trash_location
+            .as_deref()
+            .is_some_and(|location| Path::new(location).exists()));
+    }
+
+    #[test]
+    fn unwritable_audit_log_stops_the_purge() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join("cache/blob"), 5);
+        let (_bin, trasher) = scratch_trash();
+        let stored = plan_purge(
+            &[path_string(&home.path().join("cache"))],
+            &open_guard(home.path()),
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        // A directory where the log file should be cannot be appended to.
+        let data_dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(data_dir.path());
+
+        assert!(execute_purge(&stored, &open_guard(home.path()), &trasher, &audit).is_err());
+        assert!(home.path().join("cache/blob").exists());
+    }
+
+    #[test]
+    fn execute_refuses_when_a_target_changed() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join("build/out.o"), 50);
+        let (_bin, trasher) = scratch_trash();
+        let (_data_dir, audit) = scratch_audit();
+
+        let stored = plan_purge(
+            &[path_string(&home.path().join("build"))],
+            &open_guard(home.path()),
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+        write_file(&home.path().join("build/new-report.pdf"), 10);
+
+        let refusal = execute_purge(&stored, &open_guard(home.path()), &trasher, &audit)
+            .err()
+            .unwrap();
+        assert!(refusal.contains("changed since the plan"));
+        assert!(home.path().join("build/out.o").exists());
+        assert!(trasher.trashed.lock().unwrap().is_empty());
+        assert!(audit.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn guarded_paths_are_refused_with_a_reason() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join(".ssh/id_ed25519"), 400);
+        write_file(&home.path().join(".cache/pip/wheel"), 60);
+        let guard = PathGuard::new(Some(home.path()), Some(home.path()), &[]);
+
+        let stored = plan_purge(
+            &[
+                path_string(&home.path().join(".ssh")),
+                path_string(&home.path().join(".cache/pip")),
+            ],
+            &guard,
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(stored.plan.targets.len(), 1);
+        assert_eq!(stored.plan.total_bytes, 60);
+        assert_eq!(stored.plan.refused.len(), 1);
+        assert!(stored.plan.refused[0].reason.contains("SSH keys"));
+    }
+
+    #[test]
+    fn parent_dir_components_are_refused() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join(".cargo/registry/index"), 10);
+        let guard = PathGuard::new(Some(home.path()), Some(home.path()), &[]);
+
+        let stored = plan_purge(
+            &[
+                path_string(&home.path().join("..")),
+                path_string(&home.path().join(".cargo/..")),
+            ],
+            &guard,
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert!(stored.plan.targets.is_empty());
+        assert_eq!(stored.plan.refused.len(), 2);
+        assert!(stored
+            .plan
+            .refused
+            .iter()
+            .all(|refusal| refusal.reason.contains(".. components")));
+    }
+
+    #[test]
+    fn execute_rechecks_the_guard() {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join("cache/blob"), 5);
+        let (_bin, trasher) = scratch_trash();
+        let (_data_dir, audit) = scratch_audit();
+        let stored = plan_purge(
+            &[path_string(&home.path().join("cache"))],
+            &open_guard(home.path()),
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let stricter_guard = PathGuard::new(
+            None,
+            Some(home.path()),
+            &[path_string(&home.path().join("cache"))],
+        );
+        assert!(execute_purge(&stored, &stricter_guard, &trasher, &audit).is_err());
+        assert!(home.path().join("cache/blob").exists());
+    }
+
+    #[test]
+    fn failures_are_reported_per_item() {
+        struct FailingTrash;
+        impl Trasher for FailingTrash {
+            fn trash(&self, _path: &Path) -> Result<Option<PathBuf>, String> {
+                Err("trash is full".into())
+            }
+        }
+
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join("dist/bundle.js"), 5);
+        let (_data_dir, audit) = scratch_audit();
+        let stored = plan_purge(
+            &[path_string(&home.path().join("dist"))],
+            &open_guard(home.path()),
+            &RuleSet::builtin(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let report =
+            execute_purge(&stored, &open_guard(home.path()), &FailingTrash, &audit).unwrap();
+        assert_eq!(report.items[0].outcome, PurgeOutcome::Failed);
+        assert_eq!(report.items[0].error.as_deref(), Some("trash is full"));
+        assert_eq!(report.trashed_bytes, 0);
+        assert_eq!(audit.entries().unwrap()[0].outcome, PurgeOutcome::Failed);
+    }
+}
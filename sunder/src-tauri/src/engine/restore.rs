@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+use super::audit::AuditEntry;
+use super::category::Category;
+use super::purge::PurgeOutcome;
+
+// -- Shared types --
+
+/// A purged item that is still sitting in the trash.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct RestorableItem {
+    /// The audit entry id.
+    pub id: String,
+    pub original_path: String,
+    pub trash_location: String,
+    pub size_bytes: u64,
+    pub category: Category,
+    pub trashed_at: u64,
+    /// Something now exists at the original path, so restoring would
+    /// overwrite it.
+    pub conflict: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreOutcome {
+    Restored,
+    /// Left in the trash because the original path is taken.
+    Conflict,
+    Failed,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RestoreItemResult {
+    pub id: String,
+    pub path: Option<String>,
+    pub outcome: RestoreOutcome,
+    pub error: Option<String>,
+}
+
+// -- Freedesktop trash info --
+
+/// `<trash>/files/<name>` is described by `<trash>/info/<name>.trashinfo`.
+fn info_file_for(trashed: &Path) -> Option<PathBuf> {
+    let name = trashed.file_name()?;
+    let trash_dir = trashed.parent()?.parent()?;
+    let mut info_name = name.to_os_string();
+    info_name.push(".trashinfo");
+    Some(trash_dir.join("info").join(info_name))
+}
+
+/// Decodes the `%XX` escapes the spec requires in the `Path` key.
+fn percent_decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Reads the original path out of a `.trashinfo` file. Relative paths (used
+/// by per-volume trash directories) are returned as written.
+fn read_info_path(info_file: &Path) -> Option<PathBuf> {
+    let contents = std::fs::read_to_string(info_file).ok()?;
+    let mut in_section = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == "[Trash Info]";
+        } else if in_section {
+            if let Some(encoded) = line.strip_prefix("Path=") {
+                return Some(PathBuf::from(percent_decode(encoded)));
+            }
+        }
+    }
+    None
+}
+
+/// Whether `entry`'s item is still in the trash under the name the audit log
+/// recorded, rather than emptied and the name reused by something else.
+fn still_trashed(entry: &AuditEntry) -> Option<PathBuf> {
+    if entry.outcome != PurgeOutcome::Trashed {
+        return None;
+    }
+    let trashed = PathBuf::from(entry.trash_location.as_ref()?);
+    std::fs::symlink_metadata(&trashed).ok()?;
+    let info_path = read_info_path(&info_file_for(&trashed)?)?;
+    Path::new(&entry.path)
+        .ends_with(&info_path)
+        .then_some(trashed)
+}
+
+// -- Listing and restoring --
+
+/// Audit entries whose items can still be put back, newest first.
+pub fn list_restorable(entries: &[AuditEntry]) -> Vec<RestorableItem> {
+    entries
+        .iter()
+        .rev()
+        .filter_map(|entry| {
+            let trashed = still_trashed(entry)?;
+            Some(RestorableItem {
+                id: entry.id.clone(),
+                original_path: entry.path.clone(),
+                trash_location: trashed.to_string_lossy().to_string(),
+                size_bytes: entry.size_bytes,
+                category: entry.category.clone(),
+                trashed_at: entry.timestamp,
+                conflict: std::fs::symlink_metadata(&entry.path).is_ok(),
+            })
+        })
+        .collect()
+}
+
+/// Claims `original` with an empty placeholder of the same kind, so a path
+/// that appears after the conflict check is never overwritten. Renaming onto
+/// an empty directory or a file replaces the placeholder.
+fn reserve(original: &Path, is_dir: bool) -> std::io::Result<()> {
+    if is_dir {
+        std::fs::create_dir(original)
+    } else {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(original)
+            .map(drop)
+    }
+}
+
+fn restore_one(entry: &AuditEntry, trashed: &Path) -> RestoreItemResult {
+    let original = Path::new(&entry.path);
+    let result = |outcome, error: Option<String>| RestoreItemResult {
+        id: entry.id.clone(),
+        path: Some(entry.path.clone()),
+        outcome,
+        error,
+    };
+
+    let is_dir = std::fs::symlink_metadata(trashed)
+        .map(|metadata| metadata.is_dir())
+        .unwrap_or(false);
+    if let Some(parent) = original.parent() {
+        if let Err(create_error) = std::fs::create_dir_all(parent) {
+            return result(RestoreOutcome::Failed, Some(create_error.to_string()));
+        }
+    }
+
+    match reserve(original, is_dir) {
+        Ok(()) => {}
+        Err(reserve_error) if reserve_error.kind() == std::io::ErrorKind::AlreadyExists => {
+            return result(
+                RestoreOutcome::Conflict,
+                Some(format!("{} already exists", entry.path)),
+            );
+        }
+        Err(reserve_error) => {
+            return result(RestoreOutcome::Failed, Some(reserve_error.to_string()))
+        }
+    }
+
+    if let Err(rename_error) = std::fs::rename(trashed, original) {
+        let _ = if is_dir {
+            std::fs::remove_dir(original)
+        } else {
+            std::fs::remove_file(original)
+        };
+        return result(RestoreOutcome::Failed, Some(rename_error.to_string()));
+    }
+    if let Some(info_file) = info_file_for(trashed) {
+        let _ = std::fs::remove_file(info_file);
+    }
+    result(RestoreOutcome::Restored, None)
+}
+
+/// Moves the items behind `ids` back to where they were purged from. Items
+/// whose original path is now taken are left in the trash and reported as
+/// conflicts; nothing is ever overwritten.
+pub fn restore_items(ids: &[String], entries: &[AuditEntry]) -> Vec<RestoreItemResult> {
+    let by_id: HashMap<&str, &AuditEntry> = entries
+        .iter()
+        .map(|entry| (entry.id.as_str(), entry))
+        .collect();
+
+    ids.iter()
+        .map(|id| {
+            let Some(entry) = by_id.get(id.as_str()) else {
+                return RestoreItemResult {
+                    id: id.clone(),
+                    path: None,
+                    outcome: RestoreOutcome::Failed,
+                    error: Some("No audit entry with this id".into()),
+                };
+            };
+            match still_trashed(entry) {
+                Some(trashed) => restore_one(entry, &trashed),
+                None => RestoreItemResult {
+                    id: id.clone(),
+                    path: Some(entry.path.clone()),
+                    outcome: RestoreOutcome::Failed,
+                    error: Some("No longer in the trash".into()),
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A freedesktop-style trash directory next to a fake home.
+    struct Fixture {
+        _temp: tempfile::TempDir,
+        home: PathBuf,
+        trash: PathBuf,
+    }
+
+    fn fixture() -> Fixture {
+        let temp = tempfile::tempdir().unwrap();
+        let home = temp.path().join("home");
+        let trash = temp.path().join("Trash");
+        fs::create_dir_all(&home).unwrap();
+        fs::create_dir_all(trash.join("files")).unwrap();
+        fs::create_dir_all(trash.join("info")).unwrap();
+        Fixture {
+            _temp: temp,
+            home,
+            trash,
+        }
+    }
+
+    impl Fixture {
+        /// Puts `relative` (under home) into the trash as `trashed_name` and
+        /// returns its audit entry.
+        fn trash_item(&self, relative: &str, trashed_name: &str, index: usize) -> AuditEntry {
+            let original = self.home.join(relative);
+            let trashed = self.trash.join("files").join(trashed_name);
+            fs::create_dir_all(&trashed).unwrap();
+            fs::write(trashed.join("blob"), b"cached").unwrap();
+            let encoded = original.to_string_lossy().replace(' ', "%20");
+            fs::write(
+                self.trash
+                    .join("info")
+                    .join(format!("{trashed_name}.trashinfo")),
+                format!("[Trash Info]\nPath={encoded}\nDeletionDate=2026-10-01T12:00:00\n"),
+            )
+            .unwrap();
+
+            AuditEntry {
+                id: format!("plan-a/{index}"),
+                timestamp: 100 + index as u64,
+                plan_id: "plan-a".into(),
+                path: original.to_string_lossy().to_string(),
+                size_bytes: 6,
+                category: Category::PackageCaches,
+                outcome: PurgeOutcome::Trashed,
+                error: None,
+                trash_location: Some(trashed.to_string_lossy().to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(percent_decode("/home/me/My%20Files"), "/home/me/My Files");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn lists_items_still_in_the_trash_newest_first() {
+        let fixture = fixture();
+        let first = fixture.trash_item(".npm", ".npm", 0);
+        let second = fixture.trash_item("My Cache", "My Cache", 1);
+        let mut failed = fixture.trash_item("target", "target", 2);
+        failed.outcome = PurgeOutcome::Failed;
+
+        let restorable = list_restorable(&[first, second, failed]);
+        let ids: Vec<&str> = restorable.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, ["plan-a/1", "plan-a/0"]);
+        assert!(!restorable[0].conflict);
+    }
+
+    #[test]
+    fn emptied_or_reused_trash_names_are_not_restorable() {
+        let fixture = fixture();
+        let emptied = fixture.trash_item(".npm", ".npm", 0);
+        fs::remove_dir_all(fixture.trash.join("files/.npm")).unwrap();
+
+        let reused = fixture.trash_item(".cache", "cache", 1);
+        fixture.trash_item("other/cache", "cache", 2);
+
+        assert!(list_restorable(&[emptied, reused]).is_empty());
+    }
+
+    #[test]
+    fn restores_to_the_original_path() {
+        let fixture = fixture();
+        let entry = fixture.trash_item("projects/app/target", "target", 0);
+
+        let results = restore_items(&["plan-a/0".into()], &[entry]);
+        assert_eq!(results[0].outcome, RestoreOutcome::Restored);
+        assert!(fixture.home.join("projects/app/target/blob").exists());
+        assert!(!fixture.trash.join("files/target").exists());
+        assert!(!fixture.trash.join("info/target.trashinfo").exists());
+    }
+
+    #[test]
+    fn conflicts_are_detected_and_left_alone() {
+        let fixture = fixture();
+        let entry = fixture.trash_item("node_modules", "node_modules", 0);
+        fs::create_dir_all(fixture.home.join("node_modules")).unwrap();
+        fs::write(fixture.home.join("node_modules/new"), b"fresh").unwrap();
+
+        assert!(list_restorable(std::slice::from_ref(&entry))[0].conflict);
+
+        let results = restore_items(&["plan-a/0".into()], &[entry]);
+        assert_eq!(results[0].outcome, RestoreOutcome::Conflict);
+        assert!(fixture.home.join("node_modules/new").exists());
+        assert!(fixture.trash.join("files/node_modules/blob").exists());
+    }
+
+    #[test]
+    fn unknown_ids_fail_individually() {
+        let fixture = fixture();
+        let entry = fixture.trash_item(".npm", ".npm", 0);
+
+        let results = restore_items(&["plan-z/9".into(), "plan-a/0".into()], &[entry]);
+        assert_eq!(results[0].outcome, RestoreOutcome::Failed);
+        assert_eq!(results[1].outcome, RestoreOutcome::Restored);
+    }
+}
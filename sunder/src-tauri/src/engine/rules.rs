@@ -0,0 +1,634 @@
+use glob::{MatchOptions, Pattern};
+use std::path::{Path, PathBuf};
+
+use super::category::Category;
+
+pub const FALLBACK_CATEGORY: Category = Category::Other;
+
+/// Starts the id of every built-in and platform rule.
+pub const BUILTIN_ID_PREFIX: &str = "builtin:";
+
+/// How safe it is to remove what a rule matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyLevel {
+    /// Nothing of value is lost.
+    SafeToDelete,
+    /// Rebuilt or downloaded again on demand, at some cost.
+    Regenerable,
+    /// May hold state worth keeping; look before deleting.
+    Caution,
+    /// Personal or system data that should not be purged.
+    Never,
+}
+
+/// What a rule looks at. Globs support `*`, `?` and `[...]`; in `home_path`
+/// a `*` stops at `/` and `**` crosses directories.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleMatcher {
+    /// The folder's own name.
+    Name { glob: String },
+    /// The folder's path relative to the home directory.
+    HomePath { glob: String },
+    /// The folder's absolute path.
+    Path { glob: String },
+    /// A file inside the folder or next to it, optionally limited to
+    /// folders whose name matches `name`.
+    Marker {
+        #[serde(default)]
+        name: Option<String>,
+        file: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Rule {
+    pub id: String,
+    #[serde(rename = "match")]
+    pub matcher: RuleMatcher,
+    /// Higher wins. On a tie, user rules beat built-in ones and earlier
+    /// rules beat later ones.
+    #[serde(default)]
+    pub priority: i32,
+    pub category: Category,
+    pub safety: SafetyLevel,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_deserializing)]
+    pub builtin: bool,
+}
+
+/// The outcome of running a folder through the rules.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Classification {
+    pub category: Category,
+    pub safety: SafetyLevel,
+    pub description: String,
+    /// `None` when no rule matched and the fallback was used.
+    pub rule_id: Option<String>,
+}
+
+// -- Built-in rules --
+
+struct BuiltinGroup {
+    names: &'static [&'static str],
+    category: Category,
+    safety: SafetyLevel,
+    description: &'static str,
+}
+
+const BUILTIN_GROUPS: &[BuiltinGroup] = &[
+    BuiltinGroup {
+        names: &[".colima", ".docker", ".lima", ".orbstack", ".multipass"],
+        category: Category::VirtualMachines,
+        safety: SafetyLevel::Caution,
+        description: "Container and VM disk images; prune them with the engine's own tools",
+    },
+    BuiltinGroup {
+        names: &["node_modules"],
+        category: Category::PackageCaches,
+        safety: SafetyLevel::Regenerable,
+        description: "Installed dependencies; the package manager restores them",
+    },
+    BuiltinGroup {
+        names: &[
+            ".npm",
+            ".yarn",
+            ".pnpm-store",
+            ".cocoapods",
+            ".pub-cache",
+            ".nuget",
+        ],
+        category: Category::PackageCaches,
+        safety: SafetyLevel::Regenerable,
+        description: "Downloaded packages; fetched again on the next install",
+    },
+    BuiltinGroup {
+        names: &[".rustup", ".cargo", ".gradle", ".m2"],
+        category: Category::PackageCaches,
+        safety: SafetyLevel::Caution,
+        description: "Package cache kept next to toolchains, installed binaries and credentials",
+    },
+    BuiltinGroup {
+        names: &[
+            "target",
+            "dist",
+            "build",
+            ".next",
+            ".turbo",
+            "__pycache__",
+            ".angular",
+            "out",
+            ".build",
+        ],
+        category: Category::BuildArtifacts,
+        safety: SafetyLevel::Regenerable,
+        description: "Compiler output; rebuilt on the next build",
+    },
+    BuiltinGroup {
+        names: &["Library"],
+        category: Category::SystemLibraries,
+        safety: SafetyLevel::Never,
+        description: "Application support data and system libraries",
+    },
+    BuiltinGroup {
+        names: &[".Trash"],
+        category: Category::Trash,
+        safety: SafetyLevel::SafeToDelete,
+        description: "Items already moved to the trash",
+    },
+    BuiltinGroup {
+        names: &[
+            "Applications",
+            "Desktop",
+            "Documents",
+            "Downloads",
+            "Movies",
+            "Music",
+            "Pictures",
+            "Public",
+        ],
+        category: Category::UserFiles,
+        safety: SafetyLevel::Never,
+        description: "Personal files",
+    },
+];
+
+/// The category the built-in rules give a folder name, without looking at
+/// the filesystem.
+pub fn builtin_category(name: &str) -> Category {
+    BUILTIN_GROUPS
+        .iter()
+        .find(|group| group.names.contains(&name))
+        .map(|group| group.category.clone())
+        .unwrap_or(FALLBACK_CATEGORY)
+}
+
+pub fn builtin_rules() -> Vec<Rule> {
+    BUILTIN_GROUPS
+        .iter()
+        .flat_map(|group| {
+            group.names.iter().map(|name| Rule {
+                id: format!("{BUILTIN_ID_PREFIX}{name}"),
+                matcher: RuleMatcher::Name {
+                    glob: name.to_string(),
+                },
+                priority: 0,
+                category: group.category.clone(),
+                safety: group.safety,
+                description: group.description.to_string(),
+                builtin: true,
+            })
+        })
+        .collect()
+}
+
+// -- Rule set --
+
+enum CompiledMatcher {
+    Name(Pattern),
+    HomePath(Pattern),
+    Path(Pattern),
+    Marker { name: Option<Pattern>, file: String },
+}
+
+struct CompiledRule {
+    rule: Rule,
+    matcher: CompiledMatcher,
+}
+
+const PATH_MATCH: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn compile_glob(rule_id: &str, glob: &str) -> Result<Pattern, String> {
+    if glob.trim().is_empty() {
+        return Err(format!("Rule {rule_id} has an empty pattern"));
+    }
+    Pattern::new(glob).map_err(|pattern_error| format!("Rule {rule_id}: {pattern_error}"))
+}
+
+fn compile(rule: Rule) -> Result<CompiledRule, String> {
+    if rule.id.trim().is_empty() {
+        return Err("Every rule needs an id".into());
+    }
+    if rule.category.id().trim().is_empty() {
+        return Err(format!("Rule {} has no category", rule.id));
+    }
+
+    let matcher = match &rule.matcher {
+        RuleMatcher::Name { glob } => CompiledMatcher::Name(compile_glob(&rule.id, glob)?),
+        RuleMatcher::HomePath { glob } => {
+            if Path::new(glob).is_absolute() {
+                return Err(format!(
+                    "Rule {}: home_path patterns are relative to home",
+                    rule.id
+                ));
+            }
+            CompiledMatcher::HomePath(compile_glob(&rule.id, glob)?)
+        }
+        RuleMatcher::Path { glob } => {
+            if !Path::new(glob).is_absolute() {
+                return Err(format!("Rule {}: path patterns must be absolute", rule.id));
+            }
+            CompiledMatcher::Path(compile_glob(&rule.id, glob)?)
+        }
+        RuleMatcher::Marker { name, file } => {
+            if file.trim().is_empty() || file.contains('/') {
+                return Err(format!(
+                    "Rule {}: marker must be a plain file name",
+                    rule.id
+                ));
+            }
+            CompiledMatcher::Marker {
+                name: name
+                    .as_deref()
+                    .map(|name_glob| compile_glob(&rule.id, name_glob))
+                    .transpose()?,
+                file: file.clone(),
+            }
+        }
+    };
+    Ok(CompiledRule { rule, matcher })
+}
+
+/// Rules that only make sense on the running platform, such as ones for its
+/// standard directories, and folders whose children should be listed on
+/// their own instead of as one entry.
+#[derive(Debug, Default)]
+pub struct PlatformLayer {
+    pub rules: Vec<Rule>,
+    pub breakdown: Vec<PathBuf>,
+}
+
+/// Built-in and user rules, ordered so the first match wins.
+pub struct RuleSet {
+    home: Option<PathBuf>,
+    rules: Vec<CompiledRule>,
+    breakdown: Vec<PathBuf>,
+}
+
+impl RuleSet {
+    /// Fails on the first invalid user rule so a typo in the rules file is
+    /// reported instead of silently ignored.
+    pub fn new(home: Option<&Path>, user_rules: Vec<Rule>) -> Result<Self, String> {
+        Self::with_platform(home, user_rules, PlatformLayer::default())
+    }
+
+    /// Like [`RuleSet::new`], with the platform's rules evaluated after the
+    /// user's and before the built-in ones.
+    pub fn with_platform(
+        home: Option<&Path>,
+        user_rules: Vec<Rule>,
+        platform: PlatformLayer,
+    ) -> Result<Self, String> {
+        if let Some(reserved) = user_rules
+            .iter()
+            .find(|user_rule| user_rule.id.starts_with(BUILTIN_ID_PREFIX))
+        {
+            return Err(format!(
+                "Rule {}: ids starting with {BUILTIN_ID_PREFIX} are reserved for built-in rules",
+                reserved.id
+            ));
+        }
+        let mut rules = user_rules
+            .into_iter()
+            .map(|user_rule| Rule {
+                builtin: false,
+                ..user_rule
+            })
+            .chain(platform.rules)
+            .chain(builtin_rules())
+            .map(compile)
+            .collect::<Result<Vec<_>, _>>()?;
+        // Stable, so ties keep user-before-builtin and file order.
+        rules.sort_by_key(|compiled| std::cmp::Reverse(compiled.rule.priority));
+
+        Ok(Self {
+            home: home.map(Path::to_path_buf),
+            rules,
+            breakdown: platform.breakdown,
+        })
+    }
+
+    pub fn builtin() -> Self {
+        Self::new(None, Vec::new()).expect("built-in rules are valid")
+    }
+
+    /// Every rule in evaluation order.
+    pub fn rules(&self) -> Vec<Rule> {
+        self.rules
+            .iter()
+            .map(|compiled| compiled.rule.clone())
+            .collect()
+    }
+
+    /// Folders a scan lists child by child, along with any folder that
+    /// contains one of them.
+    pub fn breakdown(&self) -> &[PathBuf] {
+        &self.breakdown
+    }
+
+    fn matches(&self, matcher: &CompiledMatcher, path: &Path, name: &str) -> bool {
+        match matcher {
+            CompiledMatcher::Name(pattern) => pattern.matches(name),
+            CompiledMatcher::HomePath(pattern) => self
+                .home
+                .as_deref()
+                .and_then(|home| path.strip_prefix(home).ok())
+                .is_some_and(|relative| pattern.matches_path_with(relative, PATH_MATCH)),
+            CompiledMatcher::Path(pattern) => pattern.matches_path_with(path, PATH_MATCH),
+            CompiledMatcher::Marker {
+                name: name_pattern,
+                file,
+            } => {
+                name_pattern
+                    .as_ref()
+                    .is_none_or(|pattern| pattern.matches(name))
+                    && (path.join(file).exists()
+                        || path
+                            .parent()
+                            .is_some_and(|parent| parent.join(file).exists()))
+            }
+        }
+    }
+
+    pub fn classify(&self, path: &Path) -> Classification {
+        let name = path
+            .file_name()
+            .map(|os_name| os_name.to_string_lossy())
+            .unwrap_or_default();
+
+        self.rules
+            .iter()
+            .find(|compiled| self.matches(&compiled.matcher, path, &name))
+            .map(|compiled| Classification {
+                category: compiled.rule.category.clone(),
+                safety: compiled.rule.safety,
+                description: compiled.rule.description.clone(),
+                rule_id: Some(compiled.rule.id.clone()),
+            })
+            .unwrap_or_else(|| Classification {
+                category: FALLBACK_CATEGORY,
+                safety: SafetyLevel::Caution,
+                description: "No rule matched this folder".into(),
+                rule_id: None,
+            })
+    }
+}
+
+// -- User rules file --
+
+/// The user's own rules out of a full list such as [`RuleSet::rules`].
+/// `builtin` is not read back from the frontend, so built-in and platform
+/// rules are recognised by their id.
+pub fn user_rules_only(rules: Vec<Rule>) -> Vec<Rule> {
+    rules
+        .into_iter()
+        .filter(|rule| !rule.id.starts_with(BUILTIN_ID_PREFIX))
+        .collect()
+}
+
+/// Reads the user's rules; a missing file means no user rules.
+pub fn load_user_rules(rules_path: &Path) -> Result<Vec<Rule>, String> {
+    match std::fs::read_to_string(rules_path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|parse_error| format!("Invalid rules file: {parse_error}")),
+        Err(read_error) if read_error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(read_error) => Err(format!("Cannot read rules file: {read_error}")),
+    }
+}
+
+pub fn save_user_rules(rules_path: &Path, user_rules: &[Rule]) -> Result<(), String> {
+    if let Some(parent) = rules_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|create_error| {
+            format!("Cannot create {}: {create_error}", parent.display())
+        })?;
+    }
+    let contents = serde_json::to_string_pretty(user_rules)
+        .map_err(|encode_error| encode_error.to_string())?;
+    std::fs::write(rules_path, contents)
+        .map_err(|write_error| format!("Cannot save rules file: {write_error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn user_rule(id: &str, matcher: RuleMatcher, priority: i32, category: &str) -> Rule {
+        Rule {
+            id: id.into(),
+            matcher,
+            priority,
+            category: Category::from_id(category),
+            safety: SafetyLevel::Regenerable,
+            description: format!("{id} description"),
+            builtin: false,
+        }
+    }
+
+    fn name_glob(glob: &str) -> RuleMatcher {
+        RuleMatcher::Name { glob: glob.into() }
+    }
+
+    #[test]
+    fn builtin_rules_cover_the_original_table() {
+        let rules = RuleSet::builtin();
+        let cargo = rules.classify(Path::new("/home/me/.cargo"));
+        assert_eq!(cargo.category, Category::PackageCaches);
+        assert_eq!(cargo.safety, SafetyLevel::Caution);
+        assert_eq!(cargo.rule_id.as_deref(), Some("builtin:.cargo"));
+
+        let unknown = rules.classify(Path::new("/home/me/.ccache"));
+        assert_eq!(unknown.category, FALLBACK_CATEGORY);
+        assert_eq!(unknown.rule_id, None);
+    }
+
+    #[test]
+    fn user_rules_add_categories_and_win_ties() {
+        let rules = RuleSet::new(
+            None,
+            vec![
+                user_rule("ccache", name_glob(".*cache"), 0, "Build Artifacts"),
+                user_rule("dist", name_glob("dist"), 0, "Releases"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules.classify(Path::new("/w/.sccache")).category,
+            Category::BuildArtifacts
+        );
+        assert_eq!(
+            rules.classify(Path::new("/w/dist")).category.id(),
+            "Releases"
+        );
+    }
+
+    #[test]
+    fn priority_orders_rules() {
+        let rules = RuleSet::new(
+            None,
+            vec![
+                user_rule("low", name_glob("sdk-*"), 1, "Low"),
+                user_rule("high", name_glob("sdk-*"), 5, "High"),
+            ],
+        )
+        .unwrap();
+
+        let ordered: Vec<String> = rules.rules().into_iter().map(|rule| rule.id).collect();
+        assert_eq!(&ordered[..2], ["high", "low"]);
+        assert_eq!(
+            rules.classify(Path::new("/opt/sdk-9")).category.id(),
+            "High"
+        );
+    }
+
+    #[test]
+    fn home_path_rules_match_relative_to_home() {
+        let home = Path::new("/home/me");
+        let rules = RuleSet::new(
+            Some(home),
+            vec![user_rule(
+                "bazel",
+                RuleMatcher::HomePath {
+                    glob: ".cache/bazel*".into(),
+                },
+                0,
+                "Build Artifacts",
+            )],
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules.classify(&home.join(".cache/bazel")).category,
+            Category::BuildArtifacts
+        );
+        assert_eq!(
+            rules.classify(&home.join(".cache/nested/bazel")).category,
+            FALLBACK_CATEGORY
+        );
+        assert_eq!(
+            rules.classify(Path::new("/other/.cache/bazel")).category,
+            FALLBACK_CATEGORY
+        );
+    }
+
+    #[test]
+    fn marker_rules_look_inside_and_beside_the_folder() {
+        let workspace = tempfile::tempdir().unwrap();
+        let sdk = workspace.path().join("acme-sdk");
+        fs::create_dir_all(sdk.join("cache")).unwrap();
+        fs::write(sdk.join("ACME_SDK"), "").unwrap();
+        fs::create_dir_all(workspace.path().join("unrelated/cache")).unwrap();
+
+        let rules = RuleSet::new(
+            None,
+            vec![user_rule(
+                "acme",
+                RuleMatcher::Marker {
+                    name: Some("cache".into()),
+                    file: "ACME_SDK".into(),
+                },
+                0,
+                "SDK Caches",
+            )],
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules.classify(&sdk.join("cache")).category.id(),
+            "SDK Caches"
+        );
+        assert_eq!(
+            rules
+                .classify(&workspace.path().join("unrelated/cache"))
+                .category,
+            FALLBACK_CATEGORY
+        );
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(RuleSet::new(None, vec![user_rule("bad", name_glob("[oops"), 0, "X")]).is_err());
+        assert!(RuleSet::new(None, vec![user_rule("empty", name_glob(" "), 0, "X")]).is_err());
+        assert!(RuleSet::new(None, vec![user_rule("nocat", name_glob("x"), 0, "")]).is_err());
+        let absolute = RuleMatcher::HomePath {
+            glob: "/etc".into(),
+        };
+        assert!(RuleSet::new(None, vec![user_rule("abs", absolute, 0, "X")]).is_err());
+        let relative = RuleMatcher::Path {
+            glob: "cache/*".into(),
+        };
+        assert!(RuleSet::new(None, vec![user_rule("rel", relative, 0, "X")]).is_err());
+    }
+
+    #[test]
+    fn rules_file_round_trips() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let rules_path = config_dir.path().join("rules.json");
+        assert!(load_user_rules(&rules_path).unwrap().is_empty());
+
+        let saved = vec![user_rule(
+            "ccache",
+            name_glob(".ccache"),
+            3,
+            "Build Artifacts",
+        )];
+        save_user_rules(&rules_path, &saved).unwrap();
+        assert_eq!(load_user_rules(&rules_path).unwrap(), saved);
+
+        fs::write(
+            &rules_path,
+            r#"[{"id": "bazel", "match": {"kind": "home_path", "glob": ".cache/bazel"},
+                "category": "Build Artifacts", "safety": "regenerable"}]"#,
+        )
+        .unwrap();
+        let loaded = load_user_rules(&rules_path).unwrap();
+        assert_eq!(loaded[0].priority, 0);
+        assert!(loaded[0].description.is_empty());
+    }
+
+    #[test]
+    fn user_rules_cannot_take_builtin_ids() {
+        let impostor = user_rule(
+            &format!("{BUILTIN_ID_PREFIX}mine"),
+            name_glob(".ccache"),
+            0,
+            "Build Artifacts",
+        );
+        let refusal = RuleSet::new(None, vec![impostor]).err().unwrap();
+        assert!(refusal.contains("reserved for built-in rules"));
+    }
+
+    #[test]
+    fn listed_rules_round_trip_to_the_user_rules() {
+        let user_rules = vec![user_rule(
+            "ccache",
+            name_glob(".ccache"),
+            3,
+            "Build Artifacts",
+        )];
+        let platform = PlatformLayer {
+            rules: vec![Rule {
+                id: format!("{BUILTIN_ID_PREFIX}linux:cache"),
+                builtin: true,
+                ..user_rule("cache", name_glob(".cache"), 0, "Caches")
+            }],
+            breakdown: Vec::new(),
+        };
+        let listed = RuleSet::with_platform(None, user_rules.clone(), platform)
+            .unwrap()
+            .rules();
+
+        // What the frontend sends back: `builtin` is not deserialized.
+        let sent: Vec<Rule> =
+            serde_json::from_str(&serde_json::to_string(&listed).unwrap()).unwrap();
+        assert!(sent.iter().all(|rule| !rule.builtin));
+        assert_eq!(user_rules_only(sent), user_rules);
+    }
+}
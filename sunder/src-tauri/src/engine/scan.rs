@@ -0,0 +1,645 @@
+use rayon::prelude::*;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+
+use super::cancel::CancelToken;
+use super::category::Category;
+use super::crawler::{walk_dir, DirNode, SizeMode, WalkContext};
+use super::errors::ScanError;
+use super::mounts::SkippedMount;
+use super::progress::{FolderProgress, ProgressSink};
+use super::rules::{RuleSet, SafetyLevel};
+
+// -- Shared types --
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CategorizedFolder {
+    pub name: String,
+    pub path: String,
+    /// The size selected by `ScanOptions::size_mode`; folders sort by it.
+    pub size_bytes: u64,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    /// Bytes of hard-linked files counted here; see `DirNode::shared_bytes`.
+    pub shared_bytes: u64,
+    /// Allocated bytes of the same files. Kept for snapshots.
+    #[serde(skip)]
+    pub shared_allocated_bytes: u64,
+    pub category: Category,
+    pub safety: SafetyLevel,
+    /// What the matching rule says about this folder, for display.
+    pub description: String,
+    /// Set on the entry standing for the files directly inside a broken-down
+    /// folder. Its `path` names no real folder, so it cannot be purged.
+    pub loose_files: bool,
+    /// Subfolders of at least [`RETAINED_MIN_BYTES`], largest first. Kept for
+    /// snapshots rather than sent to the frontend.
+    #[serde(skip)]
+    pub subfolders: Vec<DirNode>,
+}
+
+/// Appended to a broken-down folder's name for the entry holding its files.
+pub const LOOSE_FILES_NAME: &str = "(files)";
+
+/// Subfolders at least this big are kept below each listed folder, so a
+/// snapshot can tell which part of a folder grew.
+pub const RETAINED_MIN_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Whether dot-folders directly under the root are scanned. On by
+    /// default, since caches like `.cargo` or `.cache` are usually the point.
+    pub include_hidden: bool,
+    /// Which size drives `size_bytes`, the totals and the sort order.
+    pub size_mode: SizeMode,
+    /// Stay on the filesystem that holds the root instead of descending into
+    /// network shares, FUSE or bind mounts found below it.
+    pub same_filesystem: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            include_hidden: true,
+            size_mode: SizeMode::default(),
+            same_filesystem: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ScanResult {
+    pub root: String,
+    pub size_mode: SizeMode,
+    /// The options the scan ran with; snapshots keep them so only
+    /// comparable scans are diffed.
+    pub options: ScanOptions,
+    pub total_size_bytes: u64,
+    pub total_apparent_bytes: u64,
+    pub total_allocated_bytes: u64,
+    pub folders: Vec<CategorizedFolder>,
+    /// Mount points that were not entered because of `same_filesystem`.
+    pub skipped_mounts: Vec<SkippedMount>,
+    /// Parts of the tree that could not be read. When non-empty, the
+    /// affected totals are lower bounds.
+    pub errors: Vec<ScanError>,
+    /// Errors beyond the reporting cap that are not listed in `errors`.
+    pub omitted_error_count: u64,
+    /// Set when the scan was cancelled before it finished; totals only cover
+    /// what had been walked up to that point.
+    pub cancelled: bool,
+    /// The snapshot this scan was saved as. `None` for cancelled scans and
+    /// when saving failed.
+    pub snapshot_id: Option<String>,
+    /// Why saving the snapshot failed, or what went wrong after it was
+    /// saved.
+    pub snapshot_error: Option<String>,
+}
+
+// -- Scan --
+
+/// Checks that a user-supplied root exists, is a directory and can be
+/// listed, and returns it in canonical form.
+pub fn validate_root(root: &str) -> Result<PathBuf, String> {
+    if root.trim().is_empty() {
+        return Err("No folder selected to scan".into());
+    }
+
+    let root_path = Path::new(root);
+    let metadata = std::fs::metadata(root_path)
+        .map_err(|metadata_error| format!("Cannot access {root}: {metadata_error}"))?;
+    if !metadata.is_dir() {
+        return Err(format!("{root} is not a directory"));
+    }
+
+    std::fs::read_dir(root_path)
+        .map_err(|read_error| format!("Cannot read {root}: {read_error}"))?;
+
+    root_path
+        .canonicalize()
+        .map_err(|canonicalize_error| format!("Cannot resolve {root}: {canonicalize_error}"))
+}
+
+/// One entry of a scan: a folder walked as a whole, or the files sitting
+/// directly inside a folder that was broken down.
+enum ScanTarget {
+    Folder(PathBuf),
+    LooseFiles {
+        parent: PathBuf,
+        files: Vec<Metadata>,
+    },
+}
+
+/// Replaces each folder in `dirs` that is, or contains, one of the
+/// `breakdown` folders with its child folders, so those are sized and
+/// classified one by one. Files directly inside a replaced folder become a
+/// single loose-files entry for it.
+fn break_down(dirs: Vec<PathBuf>, breakdown: &[PathBuf], context: &WalkContext) -> Vec<ScanTarget> {
+    let mut pending = dirs;
+    let mut listed = Vec::with_capacity(pending.len());
+    while let Some(dir) = pending.pop() {
+        let is_real_dir = std::fs::symlink_metadata(&dir).is_ok_and(|metadata| metadata.is_dir());
+        if !is_real_dir || !breakdown.iter().any(|target| target.starts_with(&dir)) {
+            listed.push(ScanTarget::Folder(dir));
+            continue;
+        }
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Listed whole, so the walk reports why it cannot be read.
+            Err(_) => {
+                listed.push(ScanTarget::Folder(dir));
+                continue;
+            }
+        };
+
+        let mut files = Vec::new();
+        for dir_entry in entries.filter_map(|entry_result| {
+            entry_result
+                .map_err(|entry_error| context.record_error(&dir, &entry_error))
+                .ok()
+        }) {
+            match dir_entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => pending.push(dir_entry.path()),
+                Ok(file_type) if file_type.is_file() => match dir_entry.metadata() {
+                    Ok(metadata) => files.push(metadata),
+                    Err(metadata_error) => context.record_error(&dir, &metadata_error),
+                },
+                Ok(_) => {}
+                Err(file_type_error) => context.record_error(&dir, &file_type_error),
+            }
+        }
+        if !files.is_empty() {
+            listed.push(ScanTarget::LooseFiles { parent: dir, files });
+        }
+    }
+    listed
+}
+
+/// Sizes and classifies every direct child folder of `root`, breaking down
+/// the folders the rules ask for.
+pub fn scan_root(
+    root: &Path,
+    options: &ScanOptions,
+    rules: &RuleSet,
+    sink: &dyn ProgressSink,
+    cancel: &CancelToken,
+) -> Result<ScanResult, String> {
+    let mut context = WalkContext::new(cancel).retaining_children_above(RETAINED_MIN_BYTES);
+    if options.same_filesystem {
+        context = context.same_filesystem_as(root);
+    }
+
+    let child_dirs: Vec<_> = std::fs::read_dir(root)
+        .map_err(|read_error| read_error.to_string())?
+        .filter_map(|entry_result| {
+            entry_result
+                .map_err(|entry_error| context.record_error(root, &entry_error))
+                .ok()
+        })
+        .filter(|dir_entry| {
+            options.include_hidden || !dir_entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .map(|dir_entry| dir_entry.path())
+        .filter(|entry_path| entry_path.is_dir())
+        .collect();
+    let targets = break_down(child_dirs, rules.breakdown(), &context);
+
+    let progress = FolderProgress::start(sink, targets.len() as u64);
+    let relative_name = |path: &Path| {
+        path.strip_prefix(root)
+            .ok()
+            .and_then(|relative| relative.to_str())
+            .unwrap_or("(unknown)")
+            .to_string()
+    };
+
+    let mut folders: Vec<CategorizedFolder> = targets
+        .into_par_iter()
+        .filter_map(|target| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+
+            let (name, path, node, classified_path, loose_files) = match target {
+                ScanTarget::Folder(child_path) => {
+                    let name = relative_name(&child_path);
+                    if context.crosses_mount(&child_path) {
+                        progress.folder_done(&name);
+                        return None;
+                    }
+                    let node = walk_dir(&child_path, &context);
+                    (name, child_path.clone(), node, child_path, false)
+                }
+                ScanTarget::LooseFiles { parent, files } => {
+                    let mut node = DirNode::default();
+                    for metadata in &files {
+                        context.add_file(&mut node, metadata);
+                    }
+                    let name = format!("{}/{LOOSE_FILES_NAME}", relative_name(&parent));
+                    (name, parent.join(LOOSE_FILES_NAME), node, parent, true)
+                }
+            };
+            progress.folder_done(&name);
+
+            let classification = rules.classify(&classified_path);
+
+            Some(CategorizedFolder {
+                name,
+                path: path.to_string_lossy().to_string(),
+                size_bytes: node.size_bytes(options.size_mode),
+                apparent_bytes: node.apparent_bytes,
+                allocated_bytes: node.allocated_bytes,
+                shared_bytes: node.shared_bytes,
+                shared_allocated_bytes: node.shared_allocated_bytes,
+                category: classification.category,
+                safety: classification.safety,
+                description: classification.description,
+                loose_files,
+                subfolders: node.children,
+            })
+        })
+        .collect();
+
+    let total_size_bytes = folders.iter().map(|folder| folder.size_bytes).sum();
+    let total_apparent_bytes = folders.iter().map(|folder| folder.apparent_bytes).sum();
+    let total_allocated_bytes = folders.iter().map(|folder| folder.allocated_bytes).sum();
+    let cancelled = cancel.is_cancelled();
+
+    if !cancelled {
+        progress.finish();
+    }
+
+    folders.sort_by_key(|folder| std::cmp::Reverse(folder.size_bytes));
+    Ok(ScanResult {
+        root: root.to_string_lossy().to_string(),
+        size_mode: options.size_mode,
+        options: options.clone(),
+        total_size_bytes,
+        total_apparent_bytes,
+        total_allocated_bytes,
+        folders,
+        skipped_mounts: context.skipped_mounts(),
+        errors: context.scan_errors(),
+        omitted_error_count: context.omitted_error_count(),
+        cancelled,
+        snapshot_id: None,
+        snapshot_error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::progress::{ChannelSink, NoopSink};
+    use crate::engine::test_support::write_file;
+    use std::fs;
+    use std::sync::mpsc;
+
+    fn sample_home() -> tempfile::TempDir {
+        let home = tempfile::tempdir().unwrap();
+        write_file(&home.path().join(".cargo/registry/cache/crate.crate"), 4000);
+        write_file(&home.path().join("Documents/report.pdf"), 300);
+        write_file(&home.path().join("Documents/notes/todo.txt"), 20);
+        write_file(&home.path().join("projects/app/main.rs"), 1000);
+        write_file(&home.path().join("loose-file.txt"), 999);
+        fs::create_dir_all(home.path().join("empty")).unwrap();
+        home
+    }
+
+    #[test]
+    fn totals_cover_every_top_level_folder() {
+        let home = sample_home();
+        let result = scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(result.folders.len(), 4);
+        assert_eq!(result.total_size_bytes, 4000 + 320 + 1000);
+        assert_eq!(result.total_apparent_bytes, result.total_size_bytes);
+        assert!(result.skipped_mounts.is_empty());
+        assert!(result.errors.is_empty());
+        assert!(!result.cancelled);
+    }
+
+    #[test]
+    fn allocated_mode_drives_size_and_totals() {
+        let home = sample_home();
+        let options = ScanOptions {
+            size_mode: SizeMode::Allocated,
+            ..ScanOptions::default()
+        };
+        let result = scan_root(
+            home.path(),
+            &options,
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(result.size_mode, SizeMode::Allocated);
+        assert_eq!(result.total_size_bytes, result.total_allocated_bytes);
+        assert_eq!(result.total_apparent_bytes, 4000 + 320 + 1000);
+        for folder in &result.folders {
+            assert_eq!(folder.size_bytes, folder.allocated_bytes);
+        }
+    }
+
+    #[test]
+    fn cancelled_scan_is_flagged_as_partial() {
+        let home = sample_home();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let result = scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &RuleSet::builtin(),
+            &NoopSink,
+            &cancel,
+        )
+        .unwrap();
+        assert!(result.cancelled);
+        assert!(result.folders.is_empty());
+        assert_eq!(result.total_size_bytes, 0);
+    }
+
+    #[test]
+    fn folders_are_categorized_and_sorted_largest_first() {
+        let home = sample_home();
+        let result = scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let summary: Vec<_> = result
+            .folders
+            .iter()
+            .map(|folder| {
+                (
+                    folder.name.as_str(),
+                    folder.size_bytes,
+                    folder.category.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (".cargo", 4000, Category::PackageCaches),
+                ("projects", 1000, Category::Other),
+                ("Documents", 320, Category::UserFiles),
+                ("empty", 0, Category::Other),
+            ]
+        );
+    }
+
+    #[test]
+    fn user_rules_classify_folders() {
+        use crate::engine::rules::{Rule, RuleMatcher};
+
+        let home = sample_home();
+        let rules = RuleSet::new(
+            Some(home.path()),
+            vec![Rule {
+                id: "projects".into(),
+                matcher: RuleMatcher::HomePath {
+                    glob: "projects".into(),
+                },
+                priority: 0,
+                category: Category::Custom("Code".into()),
+                safety: SafetyLevel::Never,
+                description: "Work in progress".into(),
+                builtin: false,
+            }],
+        )
+        .unwrap();
+        let result = scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &rules,
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let projects = result
+            .folders
+            .iter()
+            .find(|folder| folder.name == "projects")
+            .unwrap();
+        assert_eq!(projects.category.id(), "Code");
+        assert_eq!(projects.safety, SafetyLevel::Never);
+        assert_eq!(projects.description, "Work in progress");
+    }
+
+    #[test]
+    fn xdg_folders_are_broken_down_per_application() {
+        use crate::engine::xdg::{linux_layer, XdgDirs};
+
+        let home = sample_home();
+        write_file(&home.path().join(".cache/pip/http/blob"), 700);
+        write_file(&home.path().join(".cache/mozilla/firefox/cache2"), 200);
+        write_file(&home.path().join(".local/share/Trash/files/old.iso"), 900);
+        write_file(&home.path().join(".local/bin/tool"), 30);
+        let xdg = XdgDirs::resolve(home.path(), |_| None);
+        let rules =
+            RuleSet::with_platform(Some(home.path()), Vec::new(), linux_layer(&xdg)).unwrap();
+
+        let result = scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &rules,
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let category = |name: &str| {
+            result
+                .folders
+                .iter()
+                .find(|folder| folder.name == name)
+                .map(|folder| folder.category.clone())
+        };
+        assert_eq!(category(".cache/pip"), Some(Category::Caches));
+        assert_eq!(category(".cache/mozilla"), Some(Category::Caches));
+        assert_eq!(category(".local/share/Trash"), Some(Category::Trash));
+        assert_eq!(category(".local/bin"), Some(Category::Other));
+        assert_eq!(category(".cache"), None);
+        assert_eq!(category(".local"), None);
+        assert_eq!(
+            result.total_size_bytes,
+            4000 + 320 + 1000 + 700 + 200 + 900 + 30
+        );
+    }
+
+    #[test]
+    fn loose_files_in_broken_down_folders_are_listed() {
+        use crate::engine::xdg::{linux_layer, XdgDirs};
+
+        let home = sample_home();
+        write_file(&home.path().join(".cache/pip/http/blob"), 700);
+        write_file(&home.path().join(".cache/thumbnail.db"), 60);
+        write_file(&home.path().join(".cache/motd.legal-displayed"), 5);
+        let xdg = XdgDirs::resolve(home.path(), |_| None);
+        let rules =
+            RuleSet::with_platform(Some(home.path()), Vec::new(), linux_layer(&xdg)).unwrap();
+
+        let result = scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &rules,
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let loose: Vec<&CategorizedFolder> = result
+            .folders
+            .iter()
+            .filter(|folder| folder.loose_files)
+            .collect();
+        assert_eq!(loose.len(), 1);
+        assert_eq!(loose[0].name, ".cache/(files)");
+        assert_eq!(loose[0].size_bytes, 65);
+        assert!(!Path::new(&loose[0].path).exists());
+        assert_eq!(result.total_size_bytes, 4000 + 320 + 1000 + 700 + 65);
+    }
+
+    #[test]
+    fn progress_is_reported_in_order() {
+        let home = sample_home();
+        let (sender, receiver) = mpsc::channel();
+        scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &RuleSet::builtin(),
+            &ChannelSink::new(sender),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let reported: Vec<_> = receiver
+            .iter()
+            .map(|progress| progress.scanned_folders)
+            .collect();
+        assert_eq!(reported, vec![0, 1, 2, 3, 4, 4]);
+    }
+
+    #[test]
+    fn hidden_folders_can_be_skipped() {
+        let home = sample_home();
+        let options = ScanOptions {
+            include_hidden: false,
+            ..ScanOptions::default()
+        };
+        let result = scan_root(
+            home.path(),
+            &options,
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(result.folders.len(), 3);
+        assert_eq!(result.total_size_bytes, 320 + 1000);
+    }
+
+    #[test]
+    fn scans_a_nested_root() {
+        let home = sample_home();
+        let project_root = home.path().join("projects");
+        let result = scan_root(
+            &project_root,
+            &ScanOptions::default(),
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(result.folders.len(), 1);
+        assert_eq!(result.folders[0].name, "app");
+        assert_eq!(result.root, project_root.to_string_lossy());
+    }
+
+    #[test]
+    fn validate_root_rejects_files_and_missing_paths() {
+        let home = sample_home();
+        let missing = home.path().join("nope");
+        let loose_file = home.path().join("loose-file.txt");
+
+        assert!(validate_root("").is_err());
+        assert!(validate_root(&missing.to_string_lossy()).is_err());
+        assert!(validate_root(&loose_file.to_string_lossy())
+            .unwrap_err()
+            .contains("not a directory"));
+    }
+
+    #[test]
+    fn validate_root_canonicalizes() {
+        let home = sample_home();
+        let dotted = home.path().join("projects/../Documents");
+        let validated = validate_root(&dotted.to_string_lossy()).unwrap();
+
+        assert_eq!(
+            validated,
+            home.path().canonicalize().unwrap().join("Documents")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_across_folders_are_counted_once() {
+        let home = sample_home();
+        write_file(&home.path().join(".pnpm-store/v3/blob"), 700);
+        fs::create_dir_all(home.path().join("projects/app/node_modules")).unwrap();
+        fs::hard_link(
+            home.path().join(".pnpm-store/v3/blob"),
+            home.path().join("projects/app/node_modules/blob"),
+        )
+        .unwrap();
+
+        let result = scan_root(
+            home.path(),
+            &ScanOptions::default(),
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(result.total_size_bytes, 4000 + 320 + 1000 + 700);
+        let shared_total: u64 = result
+            .folders
+            .iter()
+            .map(|folder| folder.shared_bytes)
+            .sum();
+        assert_eq!(shared_total, 700);
+    }
+
+    #[test]
+    fn missing_root_is_an_error() {
+        let home = tempfile::tempdir().unwrap();
+        assert!(scan_root(
+            &home.path().join("nope"),
+            &ScanOptions::default(),
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,714 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::crawler::{DirNode, SizeMode};
+use super::purge::unix_now;
+use super::scan::{ScanOptions, ScanResult};
+
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// Oldest snapshots beyond this many are removed when a new one is saved.
+pub const MAX_SNAPSHOTS: usize = 500;
+
+const MAGIC: &[u8; 8] = b"SUNDSNAP";
+const FORMAT_VERSION: u8 = 3;
+const EXTENSION: &str = "snap";
+
+// -- Shared types --
+
+/// What a snapshot file says about itself, readable without loading its
+/// folders.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SnapshotSummary {
+    pub id: String,
+    /// Seconds since the Unix epoch.
+    pub taken_at: u64,
+    pub root: String,
+    /// What the scan included; only snapshots with equal options are diffed.
+    pub options: ScanOptions,
+    pub total_apparent_bytes: u64,
+    pub total_allocated_bytes: u64,
+    pub folder_count: u64,
+    /// Set by [`SnapshotStore::save`] when the snapshot was written but older
+    /// ones could not be removed. Never stored in the file.
+    pub warning: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotFolder {
+    /// Relative to the snapshot root: a `CategorizedFolder::name`, or one of
+    /// its retained subfolders below it.
+    pub name: String,
+    /// Sizes without the hard-linked files charged here, which another scan
+    /// may charge to a different link; see
+    /// [`DirNode::shared_bytes`](super::crawler::DirNode::shared_bytes).
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+}
+
+impl SnapshotFolder {
+    fn size_bytes(&self, mode: SizeMode) -> u64 {
+        match mode {
+            SizeMode::Apparent => self.apparent_bytes,
+            SizeMode::Allocated => self.allocated_bytes,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub summary: SnapshotSummary,
+    pub folders: Vec<SnapshotFolder>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct FolderChange {
+    pub name: String,
+    pub path: String,
+    /// `None` when the folder was not in the earlier snapshot.
+    pub before_bytes: Option<u64>,
+    /// `None` when the folder is gone from the later snapshot.
+    pub after_bytes: Option<u64>,
+    /// Zero when `below_threshold` is set.
+    pub change_bytes: i64,
+    /// Set for a subfolder recorded in only one snapshot while its parent is
+    /// in both. Subfolders under
+    /// [`RETAINED_MIN_BYTES`](super::scan::RETAINED_MIN_BYTES) are not
+    /// recorded, so the missing size is anywhere below it and the change is
+    /// unknown rather than counted from or to zero.
+    pub below_threshold: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ScanDiff {
+    pub before: SnapshotSummary,
+    pub after: SnapshotSummary,
+    pub size_mode: SizeMode,
+    pub total_change_bytes: i64,
+    /// Folders whose size changed, biggest change first either way.
+    pub changes: Vec<FolderChange>,
+}
+
+// -- Encoding --
+
+// Every integer is little-endian. A file is the magic, the format version,
+// the summary and then one record per folder:
+//
+//   taken_at u64 | root str | include_hidden u8 | same_filesystem u8
+//   size_mode u8 | apparent u64 | allocated u64 | count u64
+//   name str | apparent u64 | allocated u64      (count times)
+//
+// where a str is a u32 byte length followed by UTF-8, and size_mode is 0 for
+// apparent and 1 for allocated.
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_str(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    let len = u32::try_from(value.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "string too long"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(value.as_bytes())
+}
+
+fn size_mode_byte(size_mode: SizeMode) -> u8 {
+    match size_mode {
+        SizeMode::Apparent => 0,
+        SizeMode::Allocated => 1,
+    }
+}
+
+fn write_options(writer: &mut impl Write, options: &ScanOptions) -> io::Result<()> {
+    writer.write_all(&[
+        options.include_hidden as u8,
+        options.same_filesystem as u8,
+        size_mode_byte(options.size_mode),
+    ])
+}
+
+fn read_options(reader: &mut impl Read) -> io::Result<ScanOptions> {
+    let mut bytes = [0; 3];
+    reader.read_exact(&mut bytes)?;
+    let size_mode = match bytes[2] {
+        0 => SizeMode::Apparent,
+        1 => SizeMode::Allocated,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unknown size mode",
+            ))
+        }
+    };
+    Ok(ScanOptions {
+        include_hidden: bytes[0] != 0,
+        same_filesystem: bytes[1] != 0,
+        size_mode,
+    })
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_str(reader: &mut impl Read) -> io::Result<String> {
+    let mut len_bytes = [0; 4];
+    reader.read_exact(&mut len_bytes)?;
+    let mut bytes = Vec::new();
+    reader
+        .take(u32::from_le_bytes(len_bytes) as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() != u32::from_le_bytes(len_bytes) as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes)
+        .map_err(|utf8_error| io::Error::new(io::ErrorKind::InvalidData, utf8_error))
+}
+
+fn encode(snapshot: &Snapshot, writer: &mut impl Write) -> io::Result<()> {
+    let summary = &snapshot.summary;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[FORMAT_VERSION])?;
+    write_u64(writer, summary.taken_at)?;
+    write_str(writer, &summary.root)?;
+    write_options(writer, &summary.options)?;
+    write_u64(writer, summary.total_apparent_bytes)?;
+    write_u64(writer, summary.total_allocated_bytes)?;
+    write_u64(writer, snapshot.folders.len() as u64)?;
+    for folder in &snapshot.folders {
+        write_str(writer, &folder.name)?;
+        write_u64(writer, folder.apparent_bytes)?;
+        write_u64(writer, folder.allocated_bytes)?;
+    }
+    Ok(())
+}
+
+fn decode_summary(id: &str, reader: &mut impl Read) -> io::Result<SnapshotSummary> {
+    let mut header = [0; 9];
+    reader.read_exact(&mut header)?;
+    if &header[..8] != MAGIC || header[8] != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a snapshot this version can read",
+        ));
+    }
+    Ok(SnapshotSummary {
+        id: id.to_string(),
+        taken_at: read_u64(reader)?,
+        root: read_str(reader)?,
+        options: read_options(reader)?,
+        total_apparent_bytes: read_u64(reader)?,
+        total_allocated_bytes: read_u64(reader)?,
+        folder_count: read_u64(reader)?,
+        warning: None,
+    })
+}
+
+fn decode(id: &str, reader: &mut impl Read) -> io::Result<Snapshot> {
+    let summary = decode_summary(id, reader)?;
+    // The count comes from the file, so it only sizes the loop, never an
+    // allocation up front.
+    let mut folders = Vec::new();
+    for _ in 0..summary.folder_count {
+        folders.push(SnapshotFolder {
+            name: read_str(reader)?,
+            apparent_bytes: read_u64(reader)?,
+            allocated_bytes: read_u64(reader)?,
+        });
+    }
+    Ok(Snapshot { summary, folders })
+}
+
+// -- Store --
+
+/// Adds `nodes` and everything retained below them, named by their path
+/// under `parent_name`.
+fn push_subfolders(parent_name: &str, nodes: &[DirNode], folders: &mut Vec<SnapshotFolder>) {
+    for node in nodes {
+        let name = format!("{parent_name}/{}", node.name);
+        folders.push(SnapshotFolder {
+            name: name.clone(),
+            apparent_bytes: node.apparent_bytes - node.shared_bytes,
+            allocated_bytes: node.allocated_bytes - node.shared_allocated_bytes,
+        });
+        push_subfolders(&name, &node.children, folders);
+    }
+}
+
+fn next_snapshot_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    format!("snap-{nanos:x}")
+}
+
+/// A directory of snapshot files, one per finished scan.
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Ids name files, so anything that could step outside the store is
+    /// rejected.
+    fn file_for(&self, id: &str) -> Result<PathBuf, String> {
+        let well_formed = !id.is_empty()
+            && id
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || character == '-');
+        if !well_formed {
+            return Err(format!("{id} is not a snapshot id"));
+        }
+        Ok(self.dir.join(id).with_extension(EXTENSION))
+    }
+
+    /// Records the folder sizes of a finished scan, down to the subfolders
+    /// the scan retained, and the options it ran with. Folder sizes leave out
+    /// hard-linked files so they do not move between folders from one
+    /// snapshot to the next; the totals include them. Cancelled scans only
+    /// cover part of the tree and would show up as shrinkage in every diff,
+    /// so they are refused.
+    pub fn save(&self, scan: &ScanResult) -> Result<SnapshotSummary, String> {
+        if scan.cancelled {
+            return Err("A cancelled scan is not saved as a snapshot".into());
+        }
+        let mut folders = Vec::with_capacity(scan.folders.len());
+        for folder in &scan.folders {
+            folders.push(SnapshotFolder {
+                name: folder.name.clone(),
+                apparent_bytes: folder.apparent_bytes - folder.shared_bytes,
+                allocated_bytes: folder.allocated_bytes - folder.shared_allocated_bytes,
+            });
+            push_subfolders(&folder.name, &folder.subfolders, &mut folders);
+        }
+
+        let id = next_snapshot_id();
+        let snapshot = Snapshot {
+            summary: SnapshotSummary {
+                id: id.clone(),
+                taken_at: unix_now(),
+                root: scan.root.clone(),
+                options: scan.options.clone(),
+                total_apparent_bytes: scan.total_apparent_bytes,
+                total_allocated_bytes: scan.total_allocated_bytes,
+                folder_count: folders.len() as u64,
+                warning: None,
+            },
+            folders,
+        };
+
+        let snapshot_file = self.file_for(&id)?;
+        let snapshot_error =
+            |io_error: io::Error| format!("Snapshot {}: {io_error}", snapshot_file.display());
+        fs::create_dir_all(&self.dir).map_err(snapshot_error)?;
+        // Written aside and renamed into place, so a crash never leaves a
+        // half-written snapshot under a real id.
+        let partial_file = snapshot_file.with_extension("partial");
+        let mut writer = BufWriter::new(File::create(&partial_file).map_err(snapshot_error)?);
+        encode(&snapshot, &mut writer)
+            .and_then(|_| writer.flush())
+            .and_then(|_| fs::rename(&partial_file, &snapshot_file))
+            .map_err(|write_error| {
+                let _ = fs::remove_file(&partial_file);
+                snapshot_error(write_error)
+            })?;
+
+        // The snapshot is saved by now; old ones are simply tried again
+        // after the next scan.
+        let mut summary = snapshot.summary;
+        summary.warning = self
+            .prune(MAX_SNAPSHOTS)
+            .err()
+            .map(|prune_error| format!("Old snapshots could not be removed: {prune_error}"));
+        Ok(summary)
+    }
+
+    /// Every readable snapshot, newest first. Unreadable files are skipped
+    /// rather than failing the whole list.
+    pub fn list(&self) -> Result<Vec<SnapshotSummary>, String> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(read_error) if read_error.kind() == io::ErrorKind::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(read_error) => {
+                return Err(format!("Snapshots {}: {read_error}", self.dir.display()))
+            }
+        };
+        let mut summaries: Vec<SnapshotSummary> = entries
+            .filter_map(|entry_result| entry_result.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == EXTENSION)
+            })
+            .filter_map(|path| {
+                let id = path.file_stem()?.to_str()?.to_string();
+                let mut reader = BufReader::new(File::open(&path).ok()?);
+                decode_summary(&id, &mut reader).ok()
+            })
+            .collect();
+        summaries.sort_by(|summary_a, summary_b| {
+            (summary_b.taken_at, &summary_b.id).cmp(&(summary_a.taken_at, &summary_a.id))
+        });
+        Ok(summaries)
+    }
+
+    pub fn load(&self, id: &str) -> Result<Snapshot, String> {
+        let snapshot_file = self.file_for(id)?;
+        let file = File::open(&snapshot_file).map_err(|open_error| match open_error.kind() {
+            io::ErrorKind::NotFound => format!("No snapshot {id}"),
+            _ => format!("Snapshot {}: {open_error}", snapshot_file.display()),
+        })?;
+        decode(id, &mut BufReader::new(file))
+            .map_err(|decode_error| format!("Snapshot {}: {decode_error}", snapshot_file.display()))
+    }
+
+    fn prune(&self, keep: usize) -> Result<(), String> {
+        for stale in self.list()?.iter().skip(keep) {
+            let _ = fs::remove_file(self.file_for(&stale.id)?);
+        }
+        Ok(())
+    }
+}
+
+// -- Diff --
+
+/// Whether `name` is a retained subfolder recorded on one side only while
+/// its parent was recorded on both, directly or through parents that also
+/// crossed the threshold. Entries whose parent was never recorded are listed
+/// folders, which are recorded at any size.
+fn crossed_threshold(name: &str, sizes: &HashMap<&str, (Option<u64>, Option<u64>)>) -> bool {
+    let one_sided = |name: &str| {
+        sizes.get(name).is_some_and(|(before_bytes, after_bytes)| {
+            before_bytes.is_none() || after_bytes.is_none()
+        })
+    };
+    match name.rsplit_once('/') {
+        Some((parent, _)) if sizes.contains_key(parent) => {
+            one_sided(name) && (!one_sided(parent) || crossed_threshold(parent, sizes))
+        }
+        _ => false,
+    }
+}
+
+/// Which folders grew or shrank between `before` and `after`, measured in
+/// `size_mode`. Folders that appeared or vanished count from or to zero,
+/// except subfolders that crossed the recording threshold; see
+/// [`FolderChange::below_threshold`]. Both snapshots must cover the same
+/// root with the same scan options.
+pub fn diff_snapshots(
+    before: &Snapshot,
+    after: &Snapshot,
+    size_mode: SizeMode,
+) -> Result<ScanDiff, String> {
+    if before.summary.root != after.summary.root {
+        return Err(format!(
+            "Snapshots of different folders cannot be compared: {} and {}",
+            before.summary.root, after.summary.root
+        ));
+    }
+    if before.summary.options != after.summary.options {
+        return Err(
+            "Snapshots taken with different scan options cannot be compared; scan again with the same options"
+                .into(),
+        );
+    }
+
+    let mut sizes: HashMap<&str, (Option<u64>, Option<u64>)> = HashMap::new();
+    for folder in &before.folders {
+        sizes.entry(&folder.name).or_default().0 = Some(folder.size_bytes(size_mode));
+    }
+    for folder in &after.folders {
+        sizes.entry(&folder.name).or_default().1 = Some(folder.size_bytes(size_mode));
+    }
+
+    let root = Path::new(&after.summary.root);
+    let mut changes: Vec<FolderChange> = sizes
+        .iter()
+        .map(|(name, &(before_bytes, after_bytes))| {
+            let below_threshold = crossed_threshold(name, &sizes);
+            FolderChange {
+                name: name.to_string(),
+                path: root.join(name).to_string_lossy().to_string(),
+                before_bytes,
+                after_bytes,
+                change_bytes: if below_threshold {
+                    0
+                } else {
+                    after_bytes.unwrap_or(0) as i64 - before_bytes.unwrap_or(0) as i64
+                },
+                below_threshold,
+            }
+        })
+        .filter(|change| change.change_bytes != 0 || change.below_threshold)
+        .collect();
+    changes.sort_by(|change_a, change_b| {
+        change_b
+            .change_bytes
+            .unsigned_abs()
+            .cmp(&change_a.change_bytes.unsigned_abs())
+            .then_with(|| change_a.name.cmp(&change_b.name))
+    });
+
+    let total = |summary: &SnapshotSummary| match size_mode {
+        SizeMode::Apparent => summary.total_apparent_bytes,
+        SizeMode::Allocated => summary.total_allocated_bytes,
+    } as i64;
+    Ok(ScanDiff {
+        total_change_bytes: total(&after.summary) - total(&before.summary),
+        before: before.summary.clone(),
+        after: after.summary.clone(),
+        size_mode,
+        changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::cancel::CancelToken;
+    use crate::engine::progress::NoopSink;
+    use crate::engine::rules::RuleSet;
+    use crate::engine::scan::{scan_root, ScanOptions, RETAINED_MIN_BYTES};
+    use crate::engine::test_support::write_file;
+
+    fn scan(root: &Path) -> ScanResult {
+        scan_with(root, &ScanOptions::default())
+    }
+
+    fn scan_with(root: &Path, options: &ScanOptions) -> ScanResult {
+        scan_root(
+            root,
+            options,
+            &RuleSet::builtin(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn snapshots_round_trip_and_list_newest_first() {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("Videos/trip.mp4"), 3000);
+        write_file(&root.path().join("src/main.rs"), 100);
+        let data_dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(data_dir.path().join(SNAPSHOT_DIR));
+
+        let first = store.save(&scan(root.path())).unwrap();
+        let second = store.save(&scan(root.path())).unwrap();
+        assert_eq!(first.folder_count, 2);
+        assert_eq!(first.total_apparent_bytes, 3100);
+
+        let listed: Vec<String> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|summary| summary.id)
+            .collect();
+        assert_eq!(listed, [second.id.clone(), first.id.clone()]);
+
+        let loaded = store.load(&first.id).unwrap();
+        assert_eq!(loaded.summary, first);
+        let videos = loaded
+            .folders
+            .iter()
+            .find(|folder| folder.name == "Videos")
+            .unwrap();
+        assert_eq!(videos.apparent_bytes, 3000);
+
+        assert!(store.load("../audit").is_err());
+        fs::write(
+            data_dir.path().join(SNAPSHOT_DIR).join("junk.snap"),
+            b"SUNDSNAP",
+        )
+        .unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn diff_lists_growth_shrinkage_and_new_folders() {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("Downloads/a.iso"), 5000);
+        write_file(&root.path().join("Music/song.flac"), 800);
+        write_file(&root.path().join("Documents/cv.pdf"), 100);
+        let data_dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(data_dir.path());
+        let before = store.save(&scan(root.path())).unwrap();
+
+        write_file(&root.path().join("Downloads/b.iso"), 30_000);
+        fs::remove_file(root.path().join("Music/song.flac")).unwrap();
+        write_file(&root.path().join("Games/save.dat"), 50);
+        let after = store.save(&scan(root.path())).unwrap();
+
+        let diff = diff_snapshots(
+            &store.load(&before.id).unwrap(),
+            &store.load(&after.id).unwrap(),
+            SizeMode::Apparent,
+        )
+        .unwrap();
+        let changes: Vec<(&str, i64)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.change_bytes))
+            .collect();
+        assert_eq!(
+            changes,
+            [("Downloads", 30_000), ("Music", -800), ("Games", 50)]
+        );
+        assert_eq!(diff.changes[2].before_bytes, None);
+        assert_eq!(diff.total_change_bytes, 29_250);
+    }
+
+    #[test]
+    fn cancelled_scans_are_not_saved() {
+        let root = tempfile::tempdir().unwrap();
+        let data_dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(data_dir.path());
+        let mut cancelled = scan(root.path());
+        cancelled.cancelled = true;
+        assert!(store.save(&cancelled).is_err());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn large_subfolders_are_recorded_and_diffed() {
+        let big = RETAINED_MIN_BYTES as usize;
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("Videos/2024/trip.mp4"), big);
+        write_file(&root.path().join("Videos/clips/short.mp4"), 10);
+        let data_dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(data_dir.path());
+        let before = store.save(&scan(root.path())).unwrap();
+
+        write_file(&root.path().join("Videos/2024/more.mp4"), 1000);
+        let after = store.save(&scan(root.path())).unwrap();
+
+        let loaded = store.load(&before.id).unwrap();
+        let names: Vec<&str> = loaded
+            .folders
+            .iter()
+            .map(|folder| folder.name.as_str())
+            .collect();
+        assert_eq!(names, ["Videos", "Videos/2024"]);
+
+        let diff =
+            diff_snapshots(&loaded, &store.load(&after.id).unwrap(), SizeMode::Apparent).unwrap();
+        let changes: Vec<(&str, i64)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.change_bytes))
+            .collect();
+        assert_eq!(changes, [("Videos", 1000), ("Videos/2024", 1000)]);
+    }
+
+    #[test]
+    fn subfolders_crossing_the_threshold_are_not_counted_from_zero() {
+        let big = RETAINED_MIN_BYTES as usize;
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("Videos/2024/day1/trip.mp4"), big - 1000);
+        let data_dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(data_dir.path());
+        let before = store.save(&scan(root.path())).unwrap();
+
+        write_file(&root.path().join("Videos/2024/day1/more.mp4"), 2000);
+        let after = store.save(&scan(root.path())).unwrap();
+
+        let diff = diff_snapshots(
+            &store.load(&before.id).unwrap(),
+            &store.load(&after.id).unwrap(),
+            SizeMode::Apparent,
+        )
+        .unwrap();
+        let changes: Vec<(&str, i64, bool)> = diff
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.name.as_str(),
+                    change.change_bytes,
+                    change.below_threshold,
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("Videos", 2000, false),
+                ("Videos/2024", 0, true),
+                ("Videos/2024/day1", 0, true),
+            ]
+        );
+        assert_eq!(diff.changes[1].after_bytes, Some(big as u64 + 1000));
+    }
+
+    #[test]
+    fn snapshots_with_different_options_are_not_diffed() {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join(".cache/blob"), 500);
+        let data_dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(data_dir.path());
+
+        let with_hidden = store.save(&scan(root.path())).unwrap();
+        let without_hidden = ScanOptions {
+            include_hidden: false,
+            ..ScanOptions::default()
+        };
+        let visible_only = store
+            .save(&scan_with(root.path(), &without_hidden))
+            .unwrap();
+        assert_eq!(
+            store.load(&visible_only.id).unwrap().summary.options,
+            without_hidden
+        );
+
+        let refusal = diff_snapshots(
+            &store.load(&with_hidden.id).unwrap(),
+            &store.load(&visible_only.id).unwrap(),
+            SizeMode::Apparent,
+        )
+        .unwrap_err();
+        assert!(refusal.contains("different scan options"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linked_files_are_left_out_of_folder_sizes() {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("store/blob"), 4000);
+        write_file(&root.path().join("store/own.txt"), 10);
+        write_file(&root.path().join("project/own.txt"), 20);
+        fs::hard_link(
+            root.path().join("store/blob"),
+            root.path().join("project/blob"),
+        )
+        .unwrap();
+        let data_dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(data_dir.path());
+
+        let saved = store.save(&scan(root.path())).unwrap();
+        assert_eq!(saved.total_apparent_bytes, 4030);
+        let mut sizes: Vec<(String, u64)> = store
+            .load(&saved.id)
+            .unwrap()
+            .folders
+            .into_iter()
+            .map(|folder| (folder.name, folder.apparent_bytes))
+            .collect();
+        sizes.sort();
+        assert_eq!(
+            sizes,
+            [("project".to_string(), 20), ("store".to_string(), 10)]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Fixtures shared by the engine's unit tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::audit::{AuditLog, AUDIT_LOG_FILE};
+use super::cancel::CancelToken;
+use super::cleanup::{ToolOutput, ToolRunner};
+use super::guard::PathGuard;
+use super::purge::Trasher;
+
+/// Writes `len` zero bytes to `path`, creating its parent directories.
+pub fn write_file(path: &Path, len: usize) {
+    write_contents(path, &vec![0_u8; len]);
+}
+
+pub fn write_contents(path: &Path, contents: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// A guard scoped to a temp directory with no home protections.
+pub fn open_guard(root: &Path) -> PathGuard {
+    PathGuard::new(None, Some(root), &[])
+}
+
+/// An audit log in its own temp directory, which must outlive the log.
+pub fn scratch_audit() -> (tempfile::TempDir, AuditLog) {
+    let data_dir = tempfile::tempdir().unwrap();
+    let log = AuditLog::new(data_dir.path().join(AUDIT_LOG_FILE));
+    (data_dir, log)
+}
+
+/// Deletes outright instead of using the real trash.
+pub struct DeleteTrash;
+
+impl Trasher for DeleteTrash {
+    fn trash(&self, path: &Path) -> Result<Option<PathBuf>, String> {
+        fs::remove_dir_all(path).map_err(|remove_error| remove_error.to_string())?;
+        Ok(None)
+    }
+}
+
+/// Arguments and environment of one tool invocation.
+pub type ToolCall = (Vec<String>, Vec<(String, String)>);
+
+/// Pretends a fixed set of tools is installed. Running one deletes the
+/// directories named in `removes`.
+pub struct FakeTools {
+    pub installed: &'static [&'static str],
+    pub succeeds: bool,
+    pub removes: Vec<PathBuf>,
+    pub calls: Mutex<Vec<ToolCall>>,
+}
+
+impl FakeTools {
+    pub fn new(installed: &'static [&'static str]) -> Self {
+        Self {
+            installed,
+            succeeds: true,
+            removes: Vec::new(),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// No tool installed at all.
+    pub fn none() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl ToolRunner for FakeTools {
+    fn locate(&self, program: &str) -> Option<PathBuf> {
+        self.installed
+            .contains(&program)
+            .then(|| PathBuf::from("/usr/bin").join(program))
+    }
+
+    fn run(
+        &self,
+        _program: &Path,
+        args: &[String],
+        env: &[(String, String)],
+        _cancel: &CancelToken,
+    ) -> Result<ToolOutput, String> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((args.to_vec(), env.to_vec()));
+        if self.succeeds {
+            for removed in &self.removes {
+                fs::remove_dir_all(removed).unwrap();
+            }
+        }
+        Ok(ToolOutput {
+            success: self.succeeds,
+            output: "done".into(),
+        })
+    }
+}
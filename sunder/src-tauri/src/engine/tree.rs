@@ -0,0 +1,340 @@
+use rayon::prelude::*;
+use std::path::{Component, Path, PathBuf};
+
+use super::cancel::CancelToken;
+use super::crawler::{allocated_len, walk_dir, DirNode, SizeMode, WalkContext};
+use super::errors::ScanError;
+use super::mounts::SkippedMount;
+use super::progress::{FolderProgress, ProgressSink};
+
+/// Name given to the synthetic node that absorbs children below
+/// `min_size_bytes`.
+pub const OTHER_NODE_NAME: &str = "(other)";
+
+// -- Shared types --
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct TreeOptions {
+    /// How many levels below the requested node to include.
+    pub depth: usize,
+    /// Children smaller than this are merged into a single "(other)" node.
+    pub min_size_bytes: u64,
+    /// Which size drives `size_bytes`, sorting and collapsing.
+    pub size_mode: SizeMode,
+    /// Only used when building: stay on the root's filesystem.
+    pub same_filesystem: bool,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            min_size_bytes: 0,
+            size_mode: SizeMode::default(),
+            same_filesystem: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SizeNode {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    pub shared_bytes: u64,
+    pub file_count: u64,
+    pub children: Vec<SizeNode>,
+    /// True when deeper levels exist that were cut off by `depth`; the UI
+    /// can fetch them with `expand_node`.
+    pub expandable: bool,
+    /// True for the "(other)" node that stands in for collapsed children.
+    pub is_other: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ScanTree {
+    pub root: SizeNode,
+    pub skipped_mounts: Vec<SkippedMount>,
+    pub errors: Vec<ScanError>,
+    pub cancelled: bool,
+}
+
+/// A fully retained directory tree plus the path it was scanned from, so
+/// any level can be viewed later without going back to the disk.
+pub struct ScannedTree {
+    pub root_path: PathBuf,
+    pub root: DirNode,
+    pub skipped_mounts: Vec<SkippedMount>,
+    pub errors: Vec<ScanError>,
+}
+
+// -- Building --
+
+/// Walks `root` keeping every directory level, reporting progress once per
+/// top-level child like a regular scan does.
+pub fn build_tree(
+    root: &Path,
+    options: &TreeOptions,
+    sink: &dyn ProgressSink,
+    cancel: &CancelToken,
+) -> Result<ScannedTree, String> {
+    let mut context = WalkContext::new(cancel).retaining_children();
+    if options.same_filesystem {
+        context = context.same_filesystem_as(root);
+    }
+
+    let entries: Vec<_> = std::fs::read_dir(root)
+        .map_err(|read_error| read_error.to_string())?
+        .filter_map(|entry_result| {
+            entry_result
+                .map_err(|entry_error| context.record_error(root, &entry_error))
+                .ok()
+        })
+        .collect();
+
+    let child_dirs: Vec<_> = entries
+        .iter()
+        .filter(|dir_entry| {
+            dir_entry
+                .file_type()
+                .is_ok_and(|file_type| file_type.is_dir())
+        })
+        .map(|dir_entry| dir_entry.path())
+        .collect();
+    let loose_files: Vec<_> = entries
+        .iter()
+        .filter(|dir_entry| {
+            dir_entry
+                .file_type()
+                .is_ok_and(|file_type| file_type.is_file())
+        })
+        .filter_map(|dir_entry| {
+            dir_entry
+                .metadata()
+                .map_err(|metadata_error| context.record_error(root, &metadata_error))
+                .ok()
+        })
+        .collect();
+
+    let progress = FolderProgress::start(sink, child_dirs.len() as u64);
+
+    let mut children: Vec<DirNode> = child_dirs
+        .par_iter()
+        .filter_map(|child_path| {
+            if context.crosses_mount(child_path) {
+                progress.folder_done(&child_path.file_name().unwrap_or_default().to_string_lossy());
+                return None;
+            }
+            let child = walk_dir(child_path, &context);
+            progress.folder_done(&child.name);
+            Some(child)
+        })
+        .collect();
+    children.sort_by_key(|child| std::cmp::Reverse(child.apparent_bytes));
+
+    if !cancel.is_cancelled() {
+        progress.finish();
+    }
+
+    let mut root_node = DirNode {
+        name: root
+            .file_name()
+            .map(|os_name| os_name.to_string_lossy().to_string())
+            .unwrap_or_else(|| root.to_string_lossy().to_string()),
+        ..DirNode::default()
+    };
+    if let Ok(metadata) = std::fs::symlink_metadata(root) {
+        root_node.allocated_bytes += allocated_len(&metadata);
+    }
+    for metadata in &loose_files {
+        context.add_file(&mut root_node, metadata);
+    }
+    for child in &children {
+        root_node.add_totals(child);
+    }
+    root_node.children = children;
+
+    Ok(ScannedTree {
+        root_path: root.to_path_buf(),
+        root: root_node,
+        skipped_mounts: context.skipped_mounts(),
+        errors: context.scan_errors(),
+    })
+}
+
+// -- Viewing --
+
+impl ScannedTree {
+    /// Looks up the node at an absolute `path` inside this tree.
+    pub fn find(&self, path: &Path) -> Option<&DirNode> {
+        let relative = path.strip_prefix(&self.root_path).ok()?;
+        let mut node = &self.root;
+        for component in relative.components() {
+            let Component::Normal(segment) = component else {
+                return None;
+            };
+            let segment = segment.to_string_lossy();
+            node = node.children.iter().find(|child| child.name == segment)?;
+        }
+        Some(node)
+    }
+
+    /// Returns the subtree at `path`, trimmed to the requested depth.
+    pub fn view(&self, path: &Path, options: &TreeOptions) -> Option<SizeNode> {
+        self.find(path)
+            .map(|node| view_node(node, path, options.depth, options))
+    }
+}
+
+fn view_node(node: &DirNode, path: &Path, depth: usize, options: &TreeOptions) -> SizeNode {
+    let mut children = Vec::new();
+
+    if depth > 0 {
+        let mut sorted_children: Vec<&DirNode> = node.children.iter().collect();
+        sorted_children.sort_by_key(|child| std::cmp::Reverse(child.size_bytes(options.size_mode)));
+
+        let mut other = DirNode::default();
+        let mut collapsed_count = 0;
+
+        for child in sorted_children {
+            if child.size_bytes(options.size_mode) < options.min_size_bytes {
+                other.add_totals(child);
+                collapsed_count += 1;
+            } else {
+                children.push(view_node(
+                    child,
+                    &path.join(&child.name),
+                    depth - 1,
+                    options,
+                ));
+            }
+        }
+
+        if collapsed_count > 0 {
+            children.push(SizeNode {
+                name: OTHER_NODE_NAME.to_string(),
+                path: path.to_string_lossy().to_string(),
+                size_bytes: other.size_bytes(options.size_mode),
+                apparent_bytes: other.apparent_bytes,
+                allocated_bytes: other.allocated_bytes,
+                shared_bytes: other.shared_bytes,
+                file_count: other.file_count,
+                children: Vec::new(),
+                expandable: false,
+                is_other: true,
+            });
+        }
+    }
+
+    SizeNode {
+        name: node.name.clone(),
+        path: path.to_string_lossy().to_string(),
+        size_bytes: node.size_bytes(options.size_mode),
+        apparent_bytes: node.apparent_bytes,
+        allocated_bytes: node.allocated_bytes,
+        shared_bytes: node.shared_bytes,
+        file_count: node.file_count,
+        children,
+        expandable: depth == 0 && !node.children.is_empty(),
+        is_other: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::progress::NoopSink;
+    use crate::engine::test_support::write_file;
+    use std::fs;
+
+    fn sample_tree() -> (tempfile::TempDir, ScannedTree) {
+        let root = tempfile::tempdir().unwrap();
+        write_file(&root.path().join("Library/Caches/big.db"), 5000);
+        write_file(&root.path().join("Library/Logs/app.log"), 10);
+        write_file(&root.path().join("Library/Prefs/a.plist"), 20);
+        write_file(&root.path().join(".cache/pip/wheels/w.whl"), 800);
+        write_file(&root.path().join("notes.txt"), 3);
+
+        let tree = build_tree(
+            root.path(),
+            &TreeOptions::default(),
+            &NoopSink,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        (root, tree)
+    }
+
+    #[test]
+    fn root_totals_include_loose_files() {
+        let (_root, tree) = sample_tree();
+        assert_eq!(tree.root.apparent_bytes, 5833);
+        assert_eq!(tree.root.file_count, 5);
+    }
+
+    #[test]
+    fn view_respects_depth() {
+        let (root, tree) = sample_tree();
+        let view = tree
+            .view(
+                root.path(),
+                &TreeOptions {
+                    depth: 1,
+                    min_size_bytes: 0,
+                    ..TreeOptions::default()
+                },
+            )
+            .unwrap();
+
+        let names: Vec<_> = view
+            .children
+            .iter()
+            .map(|child| child.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Library", ".cache"]);
+        assert!(view
+            .children
+            .iter()
+            .all(|child| child.children.is_empty() && child.expandable));
+    }
+
+    #[test]
+    fn small_children_collapse_into_other() {
+        let (root, tree) = sample_tree();
+        let library = root.path().join("Library");
+        let view = tree
+            .view(
+                &library,
+                &TreeOptions {
+                    depth: 1,
+                    min_size_bytes: 100,
+                    ..TreeOptions::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(view.children.len(), 2);
+        assert_eq!(view.children[0].name, "Caches");
+        let other = &view.children[1];
+        assert!(other.is_other);
+        assert_eq!(other.size_bytes, 30);
+        assert_eq!(other.file_count, 2);
+    }
+
+    #[test]
+    fn expands_nested_nodes_from_memory() {
+        let (root, tree) = sample_tree();
+        fs::remove_dir_all(root.path().join(".cache")).unwrap();
+
+        let pip = root.path().join(".cache/pip");
+        let view = tree.view(&pip, &TreeOptions::default()).unwrap();
+        assert_eq!(view.size_bytes, 800);
+        assert_eq!(view.children[0].name, "wheels");
+        assert!(tree
+            .view(&root.path().join("missing"), &TreeOptions::default())
+            .is_none());
+    }
+}
@@ -0,0 +1,226 @@
+use glob::Pattern;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::category::Category;
+use super::rules::{PlatformLayer, Rule, RuleMatcher, SafetyLevel, BUILTIN_ID_PREFIX};
+
+// -- Base directories --
+
+/// The XDG base directories, resolved the way the spec describes: an
+/// environment variable wins when it holds an absolute path, otherwise the
+/// default under home is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XdgDirs {
+    pub home: PathBuf,
+    pub cache_home: PathBuf,
+    pub config_home: PathBuf,
+    pub data_home: PathBuf,
+    pub state_home: PathBuf,
+}
+
+impl XdgDirs {
+    pub fn resolve(home: &Path, lookup: impl Fn(&str) -> Option<OsString>) -> Self {
+        let base_dir = |variable: &str, default: &str| {
+            lookup(variable)
+                .map(PathBuf::from)
+                .filter(|configured| configured.is_absolute())
+                .unwrap_or_else(|| home.join(default))
+        };
+        Self {
+            home: home.to_path_buf(),
+            cache_home: base_dir("XDG_CACHE_HOME", ".cache"),
+            config_home: base_dir("XDG_CONFIG_HOME", ".config"),
+            data_home: base_dir("XDG_DATA_HOME", ".local/share"),
+            state_home: base_dir("XDG_STATE_HOME", ".local/state"),
+        }
+    }
+
+    pub fn from_env(home: &Path) -> Self {
+        Self::resolve(home, |variable| std::env::var_os(variable))
+    }
+}
+
+// -- Linux layer --
+
+struct LinuxLocation {
+    id: &'static str,
+    category: Category,
+    safety: SafetyLevel,
+    description: &'static str,
+}
+
+/// `path` as written and, when it differs, as resolved, so a home reached
+/// through a symlink (as on Fedora Silverblue) matches either way.
+fn path_forms(path: &Path) -> Vec<PathBuf> {
+    let mut forms = vec![path.to_path_buf()];
+    if let Ok(resolved) = path.canonicalize() {
+        if resolved != path {
+            forms.push(resolved);
+        }
+    }
+    forms
+}
+
+fn location_rules(path: &Path, children: bool, location: &LinuxLocation) -> Vec<Rule> {
+    path_forms(path)
+        .into_iter()
+        .map(|form| {
+            let mut glob = Pattern::escape(&form.to_string_lossy());
+            if children {
+                glob.push_str("/*");
+            }
+            Rule {
+                id: format!("{BUILTIN_ID_PREFIX}linux:{}", location.id),
+                matcher: RuleMatcher::Path { glob },
+                priority: 0,
+                category: location.category.clone(),
+                safety: location.safety,
+                description: location.description.to_string(),
+                builtin: true,
+            }
+        })
+        .collect()
+}
+
+/// Categories for the freedesktop locations that take up space on Linux,
+/// with `~/.cache`, `~/.local/share` and `~/.var/app` listed per application.
+pub fn linux_layer(xdg: &XdgDirs) -> PlatformLayer {
+    let trash = LinuxLocation {
+        id: "trash",
+        category: Category::Trash,
+        safety: SafetyLevel::SafeToDelete,
+        description: "Items already moved to the trash",
+    };
+    let flatpak = LinuxLocation {
+        id: "flatpak",
+        category: Category::Applications,
+        safety: SafetyLevel::Caution,
+        description: "Flatpak apps and runtimes; flatpak uninstall --unused removes stale runtimes",
+    };
+    let steam = LinuxLocation {
+        id: "steam",
+        category: Category::Games,
+        safety: SafetyLevel::Caution,
+        description: "Installed games; uninstall them from Steam",
+    };
+    let snap = LinuxLocation {
+        id: "snap",
+        category: Category::Applications,
+        safety: SafetyLevel::Caution,
+        description: "Per-user data of Snap applications, kept for each revision",
+    };
+    let flatpak_app = LinuxLocation {
+        id: "flatpak-app-data",
+        category: Category::ApplicationData,
+        safety: SafetyLevel::Caution,
+        description: "Settings, data and cache of a Flatpak app",
+    };
+    let cache = LinuxLocation {
+        id: "cache",
+        category: Category::Caches,
+        safety: SafetyLevel::Regenerable,
+        description: "Application cache; rebuilt when the application needs it",
+    };
+    let data = LinuxLocation {
+        id: "data",
+        category: Category::ApplicationData,
+        safety: SafetyLevel::Caution,
+        description: "Data an application keeps in the XDG data directory",
+    };
+    let state = LinuxLocation {
+        id: "state",
+        category: Category::ApplicationData,
+        safety: SafetyLevel::Caution,
+        description: "Logs and history kept by applications",
+    };
+    let config = LinuxLocation {
+        id: "config",
+        category: Category::ApplicationSettings,
+        safety: SafetyLevel::Never,
+        description: "Application settings",
+    };
+
+    let flatpak_apps = xdg.home.join(".var/app");
+    // Specific locations come before the catch-alls for their parents.
+    let rules = [
+        location_rules(&xdg.data_home.join("Trash"), false, &trash),
+        location_rules(&xdg.data_home.join("flatpak"), false, &flatpak),
+        location_rules(&xdg.data_home.join("Steam"), false, &steam),
+        location_rules(&xdg.home.join(".steam"), false, &steam),
+        location_rules(&xdg.home.join("snap"), false, &snap),
+        location_rules(&flatpak_apps, true, &flatpak_app),
+        location_rules(&xdg.cache_home, false, &cache),
+        location_rules(&xdg.cache_home, true, &cache),
+        location_rules(&xdg.data_home, true, &data),
+        location_rules(&xdg.state_home, false, &state),
+        location_rules(&xdg.config_home, false, &config),
+    ]
+    .concat();
+
+    let breakdown = [&xdg.cache_home, &xdg.data_home, &flatpak_apps]
+        .into_iter()
+        .flat_map(|dir| path_forms(dir))
+        .collect();
+    PlatformLayer { rules, breakdown }
+}
+
+/// The layer for the platform this build runs on.
+#[cfg(target_os = "linux")]
+pub fn platform_layer(home: Option<&Path>) -> PlatformLayer {
+    home.map(|home| linux_layer(&XdgDirs::from_env(home)))
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn platform_layer(_home: Option<&Path>) -> PlatformLayer {
+    PlatformLayer::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::rules::RuleSet;
+
+    #[test]
+    fn absolute_variables_override_the_defaults() {
+        let xdg = XdgDirs::resolve(Path::new("/home/me"), |variable| match variable {
+            "XDG_CACHE_HOME" => Some("/var/tmp/me-cache".into()),
+            "XDG_DATA_HOME" => Some("relative/share".into()),
+            _ => None,
+        });
+        assert_eq!(xdg.cache_home, PathBuf::from("/var/tmp/me-cache"));
+        assert_eq!(xdg.data_home, PathBuf::from("/home/me/.local/share"));
+        assert_eq!(xdg.config_home, PathBuf::from("/home/me/.config"));
+    }
+
+    #[test]
+    fn freedesktop_locations_are_categorized() {
+        let home = Path::new("/home/me");
+        let xdg = XdgDirs::resolve(home, |variable| {
+            (variable == "XDG_CACHE_HOME").then(|| "/home/me/.cache-alt".into())
+        });
+        let rules = RuleSet::with_platform(Some(home), Vec::new(), linux_layer(&xdg)).unwrap();
+        let category = |relative: &str| rules.classify(&home.join(relative)).category;
+
+        assert_eq!(category(".cache-alt/mozilla"), Category::Caches);
+        assert_eq!(category(".cache"), Category::Other);
+        assert_eq!(category(".local/share/Trash"), Category::Trash);
+        assert_eq!(category(".local/share/flatpak"), Category::Applications);
+        assert_eq!(category(".local/share/Steam"), Category::Games);
+        assert_eq!(
+            category(".local/share/gnome-shell"),
+            Category::ApplicationData
+        );
+        assert_eq!(category("snap"), Category::Applications);
+        assert_eq!(
+            category(".var/app/org.mozilla.firefox"),
+            Category::ApplicationData
+        );
+        assert_eq!(category(".config"), Category::ApplicationSettings);
+        assert_eq!(
+            rules.classify(&home.join(".local/share/Trash")).safety,
+            SafetyLevel::SafeToDelete
+        );
+    }
+}
@@ -1,138 +1,45 @@
-use std::path::{Path, PathBuf};
-use tauri::Emitter;
-use walkdir::WalkDir;
-
-// -- Shared types --
-
-#[derive(Clone, serde::Serialize)]
-struct ScanProgress {
-    scanned_folders: u64,
-    total_folders: u64,
-    percent: f64,
-    current_folder: String,
-}
-
-#[derive(Clone, serde::Serialize)]
-struct CategorizedFolder {
-    name: String,
-    path: String,
-    size_bytes: u64,
-    category: String,
-}
-
-#[derive(Clone, serde::Serialize)]
-struct ScanResult {
-    total_size_bytes: u64,
-    folders: Vec<CategorizedFolder>,
-}
-
-// -- Classification --
-
-fn classify_folder(name: &str) -> &'static str {
-    match name {
-        ".colima" | ".docker" | ".lima" | ".orbstack" | ".multipass" => "Virtual Machines & Containers",
-        "node_modules" | ".npm" | ".yarn" | ".pnpm-store" | ".rustup" | ".cargo"
-        | ".gradle" | ".m2" | ".cocoapods" | ".pub-cache" | ".nuget" => "Package Caches",
-        "target" | "dist" | "build" | ".next" | ".turbo" | "__pycache__"
-        | ".angular" | "out" | ".build" => "Build Artifacts",
-        "Library" => "System Libraries",
-        ".Trash" => "Trash",
-        "Applications" | "Desktop" | "Documents" | "Downloads"
-        | "Movies" | "Music" | "Pictures" | "Public" => "User Files",
-        _ => "Other",
-    }
-}
-
-// -- Commands --
-
-#[tauri::command]
-fn get_home_dir() -> Result<String, String> {
-    dirs::home_dir()
-        .map(|home_path| home_path.to_string_lossy().to_string())
-        .ok_or_else(|| "Could not resolve home directory".into())
-}
-
-#[tauri::command]
-async fn smart_scan(window: tauri::Window) -> Result<ScanResult, String> {
-    let home = dirs::home_dir().ok_or("Could not resolve home directory")?;
-    tauri::async_runtime::spawn_blocking(move || run_smart_scan(home, window))
-        .await
-        .map_err(|err| format!("Scan worker failed: {err}"))?
-}
-
-fn dir_size(path: &Path) -> u64 {
-    WalkDir::new(path)
-        .into_iter()
-        .filter_map(|entry_result| entry_result.ok())
-        .filter(|entry| entry.file_type().is_file())
-        .map(|entry| entry.metadata().map(|metadata| metadata.len()).unwrap_or(0))
-        .sum()
-}
-
-fn run_smart_scan(home: PathBuf, window: tauri::Window) -> Result<ScanResult, String> {
-    let child_dirs: Vec<_> = std::fs::read_dir(&home)
-        .map_err(|read_error| read_error.to_string())?
-        .filter_map(|entry_result| entry_result.ok())
-        .map(|dir_entry| dir_entry.path())
-        .filter(|entry_path| entry_path.is_dir())
-        .collect();
-
-    let total_folders = child_dirs.len() as u64;
-    let mut folders = Vec::new();
-    let mut total_size_bytes = 0_u64;
-
-    for (folder_index, child_path) in child_dirs.into_iter().enumerate() {
-        let name = child_path
-            .file_name()
-            .and_then(|os_name| os_name.to_str())
-            .unwrap_or("(unknown)")
-            .to_string();
-
-        let _ = window.emit(
-            "scan-progress",
-            ScanProgress {
-                scanned_folders: folder_index as u64,
-                total_folders,
-                percent: (folder_index as f64 / total_folders as f64) * 100.0,
-                current_folder: name.clone(),
-            },
-        );
-
-        let size_bytes = dir_size(&child_path);
-        total_size_bytes += size_bytes;
-
-        let category = classify_folder(&name).to_string();
-
-        folders.push(CategorizedFolder {
-            name,
-            path: child_path.to_string_lossy().to_string(),
-            size_bytes,
-            category,
-        });
-    }
-
-    let _ = window.emit(
-        "scan-progress",
-        ScanProgress {
-            scanned_folders: total_folders,
-            total_folders,
-            percent: 100.0,
-            current_folder: String::new(),
-        },
-    );
-
-    folders.sort_by(|folder_a, folder_b| folder_b.size_bytes.cmp(&folder_a.size_bytes));
-    Ok(ScanResult {
-        total_size_bytes,
-        folders,
-    })
-}
+mod commands;
+pub mod engine;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![get_home_dir, smart_scan])
+        .manage(commands::scan::ScanState::default())
+        .manage(commands::tree::TreeState::default())
+        .manage(commands::purge::PurgeState::default())
+        .manage(commands::dedupe::DedupeState::default())
+        .invoke_handler(tauri::generate_handler![
+            commands::scan::get_home_dir,
+            commands::scan::smart_scan,
+            commands::scan::scan_path,
+            commands::scan::cancel_scan,
+            commands::tree::scan_tree,
+            commands::tree::expand_node,
+            commands::purge::plan_purge,
+            commands::purge::execute_purge,
+            commands::purge::get_protected_paths,
+            commands::purge::set_protected_paths,
+            commands::audit::get_audit_log,
+            commands::restore::list_restorable,
+            commands::restore::restore_items,
+            commands::rules::get_rules,
+            commands::rules::set_rules,
+            commands::rules::list_categories,
+            commands::artifacts::scan_dev_artifacts,
+            commands::largest::largest_files,
+            commands::duplicates::find_duplicates,
+            commands::dedupe::plan_dedupe_group,
+            commands::dedupe::dedupe_group,
+            commands::snapshots::list_snapshots,
+            commands::snapshots::diff_scans,
+            commands::cleanup::preview_cache_cleanup,
+            commands::cleanup::clean_cache,
+            commands::devpurge::dev_purge_scan,
+            commands::devpurge::dev_purge_clean,
+            commands::containers::container_disk_usage,
+            commands::containers::prune_container_resources,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
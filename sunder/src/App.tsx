@@ -1,4 +1,4 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
@@ -10,16 +10,261 @@ type ScanProgress = {
   current_folder: string;
 };
 
+type SizeMode = "apparent" | "allocated";
+
+type SafetyLevel = "safe_to_delete" | "regenerable" | "caution" | "never";
+
 type CategorizedFolder = {
   name: string;
   path: string;
   size_bytes: number;
+  apparent_bytes: number;
+  allocated_bytes: number;
+  shared_bytes: number;
   category: string;
+  safety: SafetyLevel;
+  description: string;
+  loose_files: boolean;
+};
+
+type SkippedMount = {
+  path: string;
+  fs_type: string;
+};
+
+type ScanError = {
+  path: string;
+  kind: "permission_denied" | "not_found" | "other";
+  entries_affected: number;
+  message: string;
+};
+
+type ScanOptions = {
+  include_hidden: boolean;
+  size_mode: SizeMode;
+  same_filesystem: boolean;
 };
 
 type ScanResult = {
+  root: string;
+  size_mode: SizeMode;
+  options: ScanOptions;
   total_size_bytes: number;
+  total_apparent_bytes: number;
+  total_allocated_bytes: number;
   folders: CategorizedFolder[];
+  skipped_mounts: SkippedMount[];
+  errors: ScanError[];
+  omitted_error_count: number;
+  cancelled: boolean;
+  snapshot_id: string | null;
+  snapshot_error: string | null;
+};
+
+type SnapshotSummary = {
+  id: string;
+  taken_at: number;
+  root: string;
+  options: ScanOptions;
+  total_apparent_bytes: number;
+  total_allocated_bytes: number;
+  folder_count: number;
+  warning: string | null;
+};
+
+type ScanDiff = {
+  before: SnapshotSummary;
+  after: SnapshotSummary;
+  total_change_bytes: number;
+  changes: {
+    name: string;
+    path: string;
+    before_bytes: number | null;
+    after_bytes: number | null;
+    change_bytes: number;
+    below_threshold: boolean;
+  }[];
+};
+
+type PurgeTarget = {
+  path: string;
+  size_bytes: number;
+  category: string;
+  warnings: string[];
+};
+
+type RefusedTarget = {
+  path: string;
+  reason: string;
+};
+
+type PurgePlan = {
+  plan_id: string;
+  created_at: number;
+  targets: PurgeTarget[];
+  total_bytes: number;
+  refused: RefusedTarget[];
+};
+
+type PurgeOutcome =
+  | "trashed"
+  | "failed"
+  | "linked"
+  | "rolled_back"
+  | "cleaned"
+  | "removed";
+
+type PurgeItemResult = {
+  path: string;
+  size_bytes: number;
+  category: string;
+  outcome: PurgeOutcome;
+  error: string | null;
+  trash_location: string | null;
+};
+
+type PurgeReport = {
+  plan_id: string;
+  items: PurgeItemResult[];
+  trashed_bytes: number;
+};
+
+type RestoreItemResult = {
+  id: string;
+  path: string | null;
+  outcome: "restored" | "conflict" | "failed";
+  error: string | null;
+};
+
+type CleanupReport = {
+  path: string;
+  bytes_before: number;
+  bytes_after: number;
+  freed_bytes: number;
+  tool_run: { command: string; success: boolean; output: string } | null;
+  trashed: string[];
+  errors: string[];
+};
+
+type CleanupPreview = {
+  path: string;
+  size_bytes: number;
+  tool_command: string | null;
+  tool_available: boolean;
+  safe_subdirs: string[];
+  caution_subdirs: string[];
+  notes: string;
+};
+
+type DevPurgeEntry = {
+  id: string;
+  name: string;
+  description: string;
+  regeneration_cost: "low" | "medium" | "high";
+  regeneration_hint: string;
+  cleanup: CleanupPreview;
+};
+
+type DevPurgeScan = {
+  entries: DevPurgeEntry[];
+  total_size_bytes: number;
+  cancelled: boolean;
+};
+
+type LargeFile = {
+  path: string;
+  size_bytes: number;
+  modified: number | null;
+  top_level_folder: string | null;
+};
+
+type LargestFilesScan = {
+  root: string;
+  files: LargeFile[];
+  cancelled: boolean;
+};
+
+type DuplicateGroup = {
+  hash: string;
+  size_bytes: number;
+  paths: string[];
+  reclaimable_bytes: number;
+};
+
+type DuplicateScan = {
+  root: string;
+  groups: DuplicateGroup[];
+  total_reclaimable_bytes: number;
+  cancelled: boolean;
+};
+
+type LinkMethod = "hard_link" | "reflink";
+
+type DedupePlan = {
+  plan_id: string;
+  keep: string;
+  steps: { path: string; method: LinkMethod; warnings: string[] }[];
+  reclaimable_bytes: number;
+  refused: RefusedTarget[];
+};
+
+type DedupeReport = {
+  plan_id: string;
+  items: {
+    path: string;
+    method: LinkMethod;
+    outcome: PurgeOutcome;
+    error: string | null;
+  }[];
+  linked_bytes: number;
+  rolled_back: boolean;
+  warnings: string[];
+};
+
+type EngineResource = "image" | "container" | "volume" | "build_cache";
+
+type ResourceUsage = {
+  total_count: number;
+  total_bytes: number;
+  reclaimable_count: number;
+  reclaimable_bytes: number;
+};
+
+type EngineDiskUsage = {
+  socket: string;
+  images: ResourceUsage;
+  containers: ResourceUsage;
+  volumes: ResourceUsage;
+  build_cache: ResourceUsage;
+  reclaimable: {
+    resource: EngineResource;
+    id: string;
+    name: string;
+    size_bytes: number;
+    named_volume: boolean;
+  }[];
+};
+
+type PruneReport = {
+  socket: string;
+  items: {
+    resource: EngineResource;
+    id: string;
+    name: string;
+    size_bytes: number;
+    removed: boolean;
+    error: string | null;
+  }[];
+  reclaimed_bytes: number;
+  skipped: string[];
+};
+
+type CategoryInfo = {
+  id: string;
+  name: string;
+  safety: SafetyLevel;
+  description: string;
+  builtin: boolean;
 };
 
 type CategoryGroup = {
@@ -42,19 +287,26 @@ function formatBytes(bytes: number): string {
   return `${scaledValue.toFixed(decimalPlaces)} ${UNITS[unitIndex]}`;
 }
 
-function groupFoldersByCategory(folders: CategorizedFolder[]): CategoryGroup[] {
+function folderSize(folder: CategorizedFolder, sizeMode: SizeMode): number {
+  return sizeMode === "allocated" ? folder.allocated_bytes : folder.apparent_bytes;
+}
+
+function groupFoldersByCategory(
+  folders: CategorizedFolder[],
+  sizeMode: SizeMode,
+): CategoryGroup[] {
   const categoryMap = new Map<string, CategoryGroup>();
 
   for (const folder of folders) {
     const existingGroup = categoryMap.get(folder.category);
 
     if (existingGroup) {
-      existingGroup.totalBytes += folder.size_bytes;
+      existingGroup.totalBytes += folderSize(folder, sizeMode);
       existingGroup.folders.push(folder);
     } else {
       categoryMap.set(folder.category, {
         category: folder.category,
-        totalBytes: folder.size_bytes,
+        totalBytes: folderSize(folder, sizeMode),
         folders: [folder],
       });
     }
@@ -65,14 +317,21 @@ function groupFoldersByCategory(folders: CategorizedFolder[]): CategoryGroup[] {
   return allGroups;
 }
 
+// Keyed by category id; see `list_categories`.
 const CATEGORY_ICONS: Record<string, string> = {
-  "Virtual Machines & Containers": "\uD83D\uDDA5\uFE0F",
-  "Package Caches": "\uD83D\uDCE6",
-  "Build Artifacts": "\uD83D\uDD27",
-  "System Libraries": "\uD83D\uDCDA",
-  "Trash": "\uD83D\uDDD1\uFE0F",
-  "User Files": "\uD83D\uDCC1",
-  "Other": "\uD83D\uDCC2",
+  virtual_machines: "\uD83D\uDDA5\uFE0F",
+  package_caches: "\uD83D\uDCE6",
+  developer_caches: "\uD83E\uDDF0",
+  build_artifacts: "\uD83D\uDD27",
+  caches: "\uD83E\uDDF9",
+  system_libraries: "\uD83D\uDCDA",
+  applications: "\uD83D\uDCF1",
+  application_data: "\uD83D\uDDC3\uFE0F",
+  application_settings: "\u2699\uFE0F",
+  games: "\uD83C\uDFAE",
+  trash: "\uD83D\uDDD1\uFE0F",
+  user_files: "\uD83D\uDCC1",
+  other: "\uD83D\uDCC2",
 };
 
 function App() {
@@ -85,6 +344,36 @@ function App() {
   const [foldersTotal, setFoldersTotal] = useState(0);
   const [elapsedMilliseconds, setElapsedMilliseconds] = useState(0);
   const [selectedCategory, setSelectedCategory] = useState<string | null>(null);
+  const [scanRoot, setScanRoot] = useState("");
+  const [sizeMode, setSizeMode] = useState<SizeMode>("apparent");
+  const [selectedPaths, setSelectedPaths] = useState<Set<string>>(new Set());
+  const [purgePlan, setPurgePlan] = useState<PurgePlan | null>(null);
+  const [purgeReport, setPurgeReport] = useState<PurgeReport | null>(null);
+  const [restoreResults, setRestoreResults] = useState<RestoreItemResult[]>([]);
+  const [cleanupReport, setCleanupReport] = useState<CleanupReport | null>(null);
+  const [devPurge, setDevPurge] = useState<DevPurgeScan | null>(null);
+  const [engineUsage, setEngineUsage] = useState<EngineDiskUsage | null>(null);
+  const [pruneReport, setPruneReport] = useState<PruneReport | null>(null);
+  const [includeNamedVolumes, setIncludeNamedVolumes] = useState(false);
+  const [largestFiles, setLargestFiles] = useState<LargestFilesScan | null>(
+    null,
+  );
+  const [duplicates, setDuplicates] = useState<DuplicateScan | null>(null);
+  const [dedupePlan, setDedupePlan] = useState<DedupePlan | null>(null);
+  const [dedupeReport, setDedupeReport] = useState<DedupeReport | null>(null);
+  const [snapshots, setSnapshots] = useState<SnapshotSummary[]>([]);
+  const [scanDiff, setScanDiff] = useState<ScanDiff | null>(null);
+  const [categories, setCategories] = useState<Record<string, CategoryInfo>>({});
+
+  useEffect(() => {
+    invoke<CategoryInfo[]>("list_categories")
+      .then((categoryList) =>
+        setCategories(
+          Object.fromEntries(categoryList.map((category) => [category.id, category])),
+        ),
+      )
+      .catch((caughtError) => setErrorMessage(String(caughtError)));
+  }, []);
 
   async function startScan() {
     setIsScanning(true);
@@ -96,6 +385,16 @@ function App() {
     setFoldersTotal(0);
     setElapsedMilliseconds(0);
     setSelectedCategory(null);
+    setSelectedPaths(new Set());
+    setPurgePlan(null);
+    setPurgeReport(null);
+    setRestoreResults([]);
+    setCleanupReport(null);
+    setLargestFiles(null);
+    setDuplicates(null);
+    setDedupePlan(null);
+    setDedupeReport(null);
+    setScanDiff(null);
 
     const scanStartTime = Date.now();
     const elapsedTimer = window.setInterval(
@@ -116,9 +415,16 @@ function App() {
         },
       );
 
-      const result = await invoke<ScanResult>("smart_scan");
+      const trimmedRoot = scanRoot.trim();
+      const options = { size_mode: sizeMode };
+      const result = trimmedRoot
+        ? await invoke<ScanResult>("scan_path", { root: trimmedRoot, options })
+        : await invoke<ScanResult>("smart_scan", { options });
       setScanResult(result);
       setScanPercent(100);
+      setSnapshots(
+        await invoke<SnapshotSummary[]>("list_snapshots").catch(() => []),
+      );
     } catch (caughtError) {
       setErrorMessage(
         caughtError instanceof Error ? caughtError.message : String(caughtError),
@@ -131,6 +437,207 @@ function App() {
     }
   }
 
+  async function cancelScan() {
+    await invoke("cancel_scan");
+  }
+
+  function togglePath(folderPath: string) {
+    setSelectedPaths((currentPaths) => {
+      const nextPaths = new Set(currentPaths);
+      if (nextPaths.has(folderPath)) {
+        nextPaths.delete(folderPath);
+      } else {
+        nextPaths.add(folderPath);
+      }
+      return nextPaths;
+    });
+  }
+
+  async function reviewPurge() {
+    if (!scanResult) return;
+    setErrorMessage("");
+    setPurgeReport(null);
+    try {
+      const plan = await invoke<PurgePlan>("plan_purge", {
+        root: scanResult.root,
+        paths: [...selectedPaths],
+      });
+      setPurgePlan(plan);
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function confirmPurge() {
+    if (!purgePlan) return;
+    setErrorMessage("");
+    try {
+      const report = await invoke<PurgeReport>("execute_purge", {
+        planId: purgePlan.plan_id,
+      });
+      setPurgeReport(report);
+      setSelectedPaths(new Set());
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    } finally {
+      setPurgePlan(null);
+    }
+  }
+
+  async function undoPurge() {
+    if (!purgeReport) return;
+    setErrorMessage("");
+    // Audit entry ids are `<plan id>/<index in the plan>`.
+    const ids = purgeReport.items
+      .map((item, index) => ({ item, id: `${purgeReport.plan_id}/${index}` }))
+      .filter(({ item }) => item.outcome === "trashed")
+      .map(({ id }) => id);
+    try {
+      const results = await invoke<RestoreItemResult[]>("restore_items", { ids });
+      setRestoreResults(results);
+      setPurgeReport(null);
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function cleanCache(folderPath: string) {
+    if (!scanResult) return;
+    setErrorMessage("");
+    try {
+      const report = await invoke<CleanupReport>("clean_cache", {
+        root: scanResult.root,
+        path: folderPath,
+      });
+      setCleanupReport(report);
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function startDevPurge() {
+    setErrorMessage("");
+    try {
+      setDevPurge(await invoke<DevPurgeScan>("dev_purge_scan"));
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+    // No running engine is normal; the section is simply left out.
+    setEngineUsage(
+      await invoke<EngineDiskUsage>("container_disk_usage").catch(() => null),
+    );
+  }
+
+  async function pruneEngine(resource: EngineResource) {
+    if (!engineUsage) return;
+    setErrorMessage("");
+    // Only the items listed to the user are removed, by id.
+    const ids = engineUsage.reclaimable
+      .filter(
+        (item) =>
+          item.resource === resource &&
+          (!item.named_volume || includeNamedVolumes),
+      )
+      .map((item) => item.id);
+    try {
+      const report = await invoke<PruneReport>("prune_container_resources", {
+        selection: { ids, include_named_volumes: includeNamedVolumes },
+      });
+      setPruneReport(report);
+      setEngineUsage(await invoke<EngineDiskUsage>("container_disk_usage"));
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function cleanDevCache(id: string) {
+    setErrorMessage("");
+    try {
+      const report = await invoke<CleanupReport>("dev_purge_clean", { id });
+      setCleanupReport(report);
+      setDevPurge(await invoke<DevPurgeScan>("dev_purge_scan"));
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function showLargestFiles() {
+    if (!scanResult) return;
+    setErrorMessage("");
+    try {
+      setLargestFiles(
+        await invoke<LargestFilesScan>("largest_files", {
+          root: scanResult.root,
+        }),
+      );
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function showDuplicates() {
+    if (!scanResult) return;
+    setErrorMessage("");
+    try {
+      setDuplicates(
+        await invoke<DuplicateScan>("find_duplicates", {
+          root: scanResult.root,
+        }),
+      );
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function reviewDedupe(group: DuplicateGroup) {
+    if (!duplicates) return;
+    setErrorMessage("");
+    setDedupeReport(null);
+    try {
+      setDedupePlan(
+        await invoke<DedupePlan>("plan_dedupe_group", {
+          root: duplicates.root,
+          group,
+        }),
+      );
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
+  async function confirmDedupe() {
+    if (!dedupePlan) return;
+    setErrorMessage("");
+    try {
+      setDedupeReport(
+        await invoke<DedupeReport>("dedupe_group", {
+          planId: dedupePlan.plan_id,
+        }),
+      );
+      await showDuplicates();
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    } finally {
+      setDedupePlan(null);
+    }
+  }
+
+  async function compareWith(earlierId: string) {
+    if (!scanResult?.snapshot_id || !earlierId) return;
+    setErrorMessage("");
+    try {
+      setScanDiff(
+        await invoke<ScanDiff>("diff_scans", {
+          a: earlierId,
+          b: scanResult.snapshot_id,
+          sizeMode,
+        }),
+      );
+    } catch (caughtError) {
+      setErrorMessage(String(caughtError));
+    }
+  }
+
   function selectCategory(categoryName: string) {
     setSelectedCategory((currentCategory) =>
       currentCategory === categoryName ? null : categoryName,
@@ -138,9 +645,15 @@ function App() {
   }
 
   const categoryGroups = scanResult
-    ? groupFoldersByCategory(scanResult.folders)
+    ? groupFoldersByCategory(scanResult.folders, sizeMode)
     : [];
 
+  const totalBytes = scanResult
+    ? sizeMode === "allocated"
+      ? scanResult.total_allocated_bytes
+      : scanResult.total_apparent_bytes
+    : 0;
+
   const elapsedSeconds = (elapsedMilliseconds / 1000).toFixed(1);
 
   return (
@@ -156,6 +669,16 @@ function App() {
           <button className="scan-circle" onClick={startScan}>
             <span className="scan-circle-label">Scan</span>
           </button>
+          <input
+            className="root-input"
+            value={scanRoot}
+            onChange={(changeEvent) => setScanRoot(changeEvent.target.value)}
+            placeholder="Folder to scan (defaults to home)"
+            spellCheck={false}
+          />
+          <button className="cancel-btn" onClick={startDevPurge}>
+            Dev-Purge
+          </button>
         </div>
       )}
 
@@ -169,24 +692,428 @@ function App() {
             {foldersScanned.toLocaleString()}/{foldersTotal.toLocaleString()} folders
             &middot; {elapsedSeconds}s
           </p>
+          <button className="cancel-btn" onClick={cancelScan}>
+            Cancel
+          </button>
         </div>
       )}
 
       {errorMessage && <p className="error">{errorMessage}</p>}
 
+      {devPurge && (
+        <section className="purge-panel">
+          <p className="summary-label">
+            Dev-Purge &middot; {formatBytes(devPurge.total_size_bytes)} in developer
+            caches
+          </p>
+          <ul className="detail-folder-list">
+            {devPurge.entries.map((entry) => (
+              <li key={entry.id} className="purge-target">
+                <div className="detail-folder-row">
+                  <span className="detail-folder-name" title={entry.cleanup.path}>
+                    {entry.name}
+                    <span className={`dev-purge-cost ${entry.regeneration_cost}`}>
+                      {entry.regeneration_cost} cost
+                    </span>
+                  </span>
+                  <button
+                    className="cancel-btn"
+                    onClick={() => cleanDevCache(entry.id)}
+                    disabled={
+                      !entry.cleanup.tool_available &&
+                      entry.cleanup.safe_subdirs.length === 0
+                    }
+                    title={entry.cleanup.notes}
+                  >
+                    Clean
+                  </button>
+                  <span className="detail-folder-size numeric">
+                    {formatBytes(entry.cleanup.size_bytes)}
+                  </span>
+                </div>
+                <p className="dev-purge-hint">
+                  {entry.description}. {entry.regeneration_hint}.
+                </p>
+              </li>
+            ))}
+          </ul>
+          {engineUsage && (
+            <>
+              <p className="summary-label">
+                Container engine &middot; {engineUsage.socket}
+              </p>
+              <ul className="detail-folder-list">
+                {(
+                  [
+                    ["image", "Dangling images", engineUsage.images],
+                    ["container", "Stopped containers", engineUsage.containers],
+                    ["volume", "Unused volumes", engineUsage.volumes],
+                    ["build_cache", "Build cache", engineUsage.build_cache],
+                  ] as [EngineResource, string, ResourceUsage][]
+                ).map(([resource, label, usage]) => (
+                  <li key={resource} className="detail-folder-row">
+                    <span className="detail-folder-name">
+                      {label}{" "}
+                      <span className="numeric">
+                        ({usage.reclaimable_count} of {usage.total_count})
+                      </span>
+                    </span>
+                    <button
+                      className="cancel-btn"
+                      onClick={() => pruneEngine(resource)}
+                      disabled={
+                        usage.reclaimable_count === 0 &&
+                        !(resource === "volume" && includeNamedVolumes)
+                      }
+                    >
+                      Prune
+                    </button>
+                    <span className="detail-folder-size numeric">
+                      {formatBytes(usage.reclaimable_bytes)}
+                    </span>
+                  </li>
+                ))}
+              </ul>
+              {engineUsage.reclaimable.some((item) => item.named_volume) && (
+                <label className="summary-notice">
+                  <input
+                    type="checkbox"
+                    checked={includeNamedVolumes}
+                    onChange={() => setIncludeNamedVolumes(!includeNamedVolumes)}
+                  />{" "}
+                  Also prune unused named volumes:{" "}
+                  {engineUsage.reclaimable
+                    .filter((item) => item.named_volume)
+                    .map((item) => item.name)
+                    .join(", ")}
+                  . They often hold databases and other data that is not
+                  downloaded again.
+                </label>
+              )}
+            </>
+          )}
+          {pruneReport && (
+            <>
+              <p className="numeric">
+                Reclaimed {formatBytes(pruneReport.reclaimed_bytes)}
+              </p>
+              {pruneReport.items
+                .filter((item) => item.error)
+                .map((item) => (
+                  <p key={item.id} className="error">
+                    {item.name}: {item.error}
+                  </p>
+                ))}
+              {pruneReport.skipped.length > 0 && (
+                <p className="summary-notice">
+                  {pruneReport.skipped.length} item(s) were no longer reclaimable
+                  and were left alone
+                </p>
+              )}
+            </>
+          )}
+          <button className="cancel-btn" onClick={() => setDevPurge(null)}>
+            Close
+          </button>
+        </section>
+      )}
+
+      {cleanupReport && (
+        <section className="purge-panel">
+          <p className="summary-label">
+            Freed {formatBytes(cleanupReport.freed_bytes)} from{" "}
+            {cleanupReport.path}
+          </p>
+          {cleanupReport.tool_run && (
+            <details className="summary-notice">
+              <summary>{cleanupReport.tool_run.command}</summary>
+              <pre>{cleanupReport.tool_run.output}</pre>
+            </details>
+          )}
+          {cleanupReport.errors.map((cleanupError) => (
+            <p key={cleanupError} className="error">
+              {cleanupError}
+            </p>
+          ))}
+        </section>
+      )}
+
       {scanResult && (
         <>
           <section className="summary-card">
-            <p className="summary-label">Total Size</p>
-            <p className="summary-size numeric">
-              {formatBytes(scanResult.total_size_bytes)}
-            </p>
+            <p className="summary-label">Total Size &middot; {scanResult.root}</p>
+            <p className="summary-size numeric">{formatBytes(totalBytes)}</p>
             <p className="summary-meta numeric">
-              {scanResult.total_size_bytes.toLocaleString()} bytes &middot;{" "}
+              {totalBytes.toLocaleString()} bytes &middot;{" "}
               {scanResult.folders.length} folders &middot; {elapsedSeconds}s
             </p>
+            <div className="size-mode-toggle">
+              <button
+                className={sizeMode === "apparent" ? "active" : ""}
+                onClick={() => setSizeMode("apparent")}
+              >
+                Apparent size
+              </button>
+              <button
+                className={sizeMode === "allocated" ? "active" : ""}
+                onClick={() => setSizeMode("allocated")}
+              >
+                Disk usage
+              </button>
+            </div>
+            <button className="cancel-btn" onClick={showLargestFiles}>
+              Largest files
+            </button>
+            <button className="cancel-btn" onClick={showDuplicates}>
+              Duplicates
+            </button>
+            {scanResult.snapshot_id && (
+              <select
+                className="root-input"
+                value={scanDiff?.before.id ?? ""}
+                onChange={(changeEvent) => compareWith(changeEvent.target.value)}
+              >
+                <option value="">Compare with an earlier scan</option>
+                {snapshots
+                  .filter(
+                    (snapshot) =>
+                      snapshot.root === scanResult.root &&
+                      snapshot.id !== scanResult.snapshot_id,
+                  )
+                  .map((snapshot) => (
+                    <option key={snapshot.id} value={snapshot.id}>
+                      {new Date(snapshot.taken_at * 1000).toLocaleString()}
+                    </option>
+                  ))}
+              </select>
+            )}
+            {scanResult.cancelled && (
+              <p className="summary-warning">
+                Scan cancelled &mdash; totals only cover what was scanned.
+              </p>
+            )}
+            {scanResult.snapshot_error && (
+              <p className="summary-warning">
+                Snapshot: {scanResult.snapshot_error}
+              </p>
+            )}
+            {scanResult.errors.length > 0 && (
+              <details className="summary-notice summary-warning">
+                <summary>
+                  Some folders couldn't be read &mdash; totals are lower bounds
+                </summary>
+                <ul>
+                  {scanResult.errors.map((scanError) => (
+                    <li key={`${scanError.path}:${scanError.kind}`}>
+                      {scanError.path}{" "}
+                      <span className="numeric">
+                        ({scanError.message}
+                        {scanError.entries_affected > 1
+                          ? `, ${scanError.entries_affected.toLocaleString()} entries`
+                          : ""}
+                        )
+                      </span>
+                    </li>
+                  ))}
+                  {scanResult.omitted_error_count > 0 && (
+                    <li>
+                      and {scanResult.omitted_error_count.toLocaleString()} more
+                    </li>
+                  )}
+                </ul>
+              </details>
+            )}
+            {scanResult.skipped_mounts.length > 0 && (
+              <details className="summary-notice">
+                <summary>
+                  {scanResult.skipped_mounts.length} mount
+                  {scanResult.skipped_mounts.length === 1 ? "" : "s"} not
+                  scanned
+                </summary>
+                <ul>
+                  {scanResult.skipped_mounts.map((mount) => (
+                    <li key={mount.path}>
+                      {mount.path} <span className="numeric">({mount.fs_type})</span>
+                    </li>
+                  ))}
+                </ul>
+              </details>
+            )}
           </section>
 
+          {largestFiles && (
+            <section className="purge-panel">
+              <p className="summary-label">
+                {largestFiles.files.length} largest files &middot;{" "}
+                {largestFiles.root}
+              </p>
+              <ul className="detail-folder-list">
+                {largestFiles.files.map((file) => (
+                  <li key={file.path} className="detail-folder-row">
+                    <span
+                      className="detail-folder-name"
+                      title={file.top_level_folder ?? largestFiles.root}
+                    >
+                      {file.path}
+                    </span>
+                    <span className="detail-folder-size numeric">
+                      {formatBytes(file.size_bytes)}
+                    </span>
+                  </li>
+                ))}
+              </ul>
+              <button className="cancel-btn" onClick={() => setLargestFiles(null)}>
+                Close
+              </button>
+            </section>
+          )}
+
+          {scanDiff && (
+            <section className="purge-panel">
+              <p
+                className="summary-label"
+                title="Folder changes leave out hard-linked files, which can be counted under a different link each scan"
+              >
+                Since {new Date(scanDiff.before.taken_at * 1000).toLocaleString()}{" "}
+                &middot; {scanDiff.total_change_bytes < 0 ? "-" : "+"}
+                {formatBytes(Math.abs(scanDiff.total_change_bytes))}
+              </p>
+              <ul className="detail-folder-list">
+                {scanDiff.changes.map((change) => (
+                  <li key={change.path} className="detail-folder-row">
+                    <span className="detail-folder-name" title={change.path}>
+                      {change.name}
+                      {change.below_threshold
+                        ? " (too small to record in one scan)"
+                        : change.before_bytes === null && " (new)"}
+                    </span>
+                    {change.below_threshold ? (
+                      <span className="detail-folder-size numeric">
+                        {change.after_bytes !== null
+                          ? `now ${formatBytes(change.after_bytes)}`
+                          : `was ${formatBytes(change.before_bytes ?? 0)}`}
+                      </span>
+                    ) : (
+                      <span
+                        className={`detail-folder-size numeric ${
+                          change.change_bytes > 0 ? "error" : ""
+                        }`}
+                      >
+                        {change.change_bytes < 0 ? "-" : "+"}
+                        {formatBytes(Math.abs(change.change_bytes))}
+                      </span>
+                    )}
+                  </li>
+                ))}
+              </ul>
+              <button className="cancel-btn" onClick={() => setScanDiff(null)}>
+                Close
+              </button>
+            </section>
+          )}
+
+          {duplicates && (
+            <section className="purge-panel">
+              <p className="summary-label">
+                Duplicates &middot;{" "}
+                {formatBytes(duplicates.total_reclaimable_bytes)} reclaimable
+              </p>
+              <ul className="detail-folder-list">
+                {duplicates.groups.map((group) => (
+                  <li key={group.hash} className="purge-target">
+                    <div className="detail-folder-row">
+                      <span className="detail-folder-name numeric">
+                        {group.paths.length} copies of{" "}
+                        {formatBytes(group.size_bytes)}
+                      </span>
+                      <button
+                        className="cancel-btn"
+                        onClick={() => reviewDedupe(group)}
+                        title="Replace the copies with links to one of them"
+                      >
+                        Link copies
+                      </button>
+                      <span className="detail-folder-size numeric">
+                        {formatBytes(group.reclaimable_bytes)}
+                      </span>
+                    </div>
+                    <ul>
+                      {group.paths.map((path) => (
+                        <li key={path} className="dev-purge-hint">
+                          {path}
+                        </li>
+                      ))}
+                    </ul>
+                  </li>
+                ))}
+              </ul>
+              {dedupePlan && (
+                <>
+                  <p className="summary-label">
+                    Dry run &middot; keep {dedupePlan.keep}
+                  </p>
+                  <ul className="detail-folder-list">
+                    {dedupePlan.steps.map((step) => (
+                      <li key={step.path} className="purge-target">
+                        <div className="detail-folder-row">
+                          <span className="detail-folder-name">{step.path}</span>
+                          <span className="detail-folder-size">
+                            {step.method === "reflink" ? "reflink" : "hard link"}
+                          </span>
+                        </div>
+                        {step.warnings.map((warning) => (
+                          <p key={warning} className="summary-warning">
+                            {warning}
+                          </p>
+                        ))}
+                      </li>
+                    ))}
+                  </ul>
+                  {dedupePlan.refused.map((item) => (
+                    <p key={item.path} className="summary-warning">
+                      {item.path} &middot; {item.reason}
+                    </p>
+                  ))}
+                  <button
+                    className="purge-btn"
+                    onClick={confirmDedupe}
+                    disabled={dedupePlan.steps.length === 0}
+                  >
+                    Link {dedupePlan.steps.length} copies, free{" "}
+                    {formatBytes(dedupePlan.reclaimable_bytes)}
+                  </button>
+                  <button className="cancel-btn" onClick={() => setDedupePlan(null)}>
+                    Back
+                  </button>
+                </>
+              )}
+              {dedupeReport && (
+                <>
+                  <p className="numeric">
+                    {dedupeReport.rolled_back
+                      ? "A copy could not be linked; every copy was put back"
+                      : `Freed ${formatBytes(dedupeReport.linked_bytes)}`}
+                  </p>
+                  {dedupeReport.items
+                    .filter((item) => item.error)
+                    .map((item) => (
+                      <p key={item.path} className="error">
+                        {item.path}: {item.error}
+                      </p>
+                    ))}
+                  {dedupeReport.warnings.map((warning) => (
+                    <p key={warning} className="summary-warning">
+                      {warning}
+                    </p>
+                  ))}
+                </>
+              )}
+              <button className="cancel-btn" onClick={() => setDuplicates(null)}>
+                Close
+              </button>
+            </section>
+          )}
+
           <section className="categories-split">
             <div className="category-list">
               {categoryGroups.map((group) => (
@@ -196,10 +1123,11 @@ function App() {
                     selectedCategory === group.category ? "active" : ""
                   }`}
                   onClick={() => selectCategory(group.category)}
+                  title={categories[group.category]?.description}
                 >
                   <span className="category-row-label">
                     {CATEGORY_ICONS[group.category] || "\uD83D\uDCC2"}{" "}
-                    {group.category}
+                    {categories[group.category]?.name ?? group.category}
                   </span>
                   <strong className="numeric">
                     {formatBytes(group.totalBytes)}
@@ -217,15 +1145,52 @@ function App() {
                     )
                     ?.folders.sort(
                       (folderA, folderB) =>
-                        folderB.size_bytes - folderA.size_bytes,
+                        folderSize(folderB, sizeMode) -
+                        folderSize(folderA, sizeMode),
                     )
                     .map((folder) => (
                       <li key={folder.path} className="detail-folder-row">
-                        <span className="detail-folder-name">
+                        <input
+                          type="checkbox"
+                          className="detail-folder-check"
+                          checked={selectedPaths.has(folder.path)}
+                          onChange={() => togglePath(folder.path)}
+                          disabled={folder.loose_files}
+                        />
+                        <span
+                          className="detail-folder-name"
+                          title={folder.description}
+                        >
                           {folder.name}
+                          {(folder.safety === "caution" ||
+                            folder.safety === "never") && (
+                            <span
+                              className={`detail-folder-safety ${folder.safety}`}
+                            >
+                              {folder.safety === "never" ? "keep" : "caution"}
+                            </span>
+                          )}
+                          {folder.shared_bytes > 0 && (
+                            <span
+                              className="detail-folder-shared numeric"
+                              title="Hard-linked elsewhere: deleting this folder may free less than shown"
+                            >
+                              {formatBytes(folder.shared_bytes)} shared
+                            </span>
+                          )}
                         </span>
+                        {folder.category === "package_caches" &&
+                          !folder.loose_files && (
+                            <button
+                              className="cancel-btn"
+                              onClick={() => cleanCache(folder.path)}
+                              title="Run the ecosystem's own cleanup, or trash only safe subfolders"
+                            >
+                              Clean
+                            </button>
+                          )}
                         <span className="detail-folder-size numeric">
-                          {formatBytes(folder.size_bytes)}
+                          {formatBytes(folderSize(folder, sizeMode))}
                         </span>
                       </li>
                     ))}
@@ -236,6 +1201,103 @@ function App() {
             </div>
           </section>
 
+          {selectedPaths.size > 0 && !purgePlan && (
+            <button className="purge-btn" onClick={reviewPurge}>
+              Review purge ({selectedPaths.size})
+            </button>
+          )}
+
+          {purgePlan && (
+            <section className="purge-panel">
+              <p className="summary-label">Dry run &middot; nothing moved yet</p>
+              <ul className="detail-folder-list">
+                {purgePlan.targets.map((target) => (
+                  <li key={target.path} className="purge-target">
+                    <div className="detail-folder-row">
+                      <span className="detail-folder-name">{target.path}</span>
+                      <span className="detail-folder-size numeric">
+                        {formatBytes(target.size_bytes)}
+                      </span>
+                    </div>
+                    {target.warnings.map((warning) => (
+                      <p key={warning} className="summary-warning">
+                        {warning}
+                      </p>
+                    ))}
+                  </li>
+                ))}
+              </ul>
+              {purgePlan.refused.length > 0 && (
+                <details className="summary-notice">
+                  <summary>{purgePlan.refused.length} protected, left out</summary>
+                  <ul>
+                    {purgePlan.refused.map((item) => (
+                      <li key={item.path}>
+                        {item.path} &middot; {item.reason}
+                      </li>
+                    ))}
+                  </ul>
+                </details>
+              )}
+              <p className="numeric">
+                Move {formatBytes(purgePlan.total_bytes)} to the trash?
+              </p>
+              <button className="purge-btn" onClick={confirmPurge}>
+                Move to Trash
+              </button>
+              <button className="cancel-btn" onClick={() => setPurgePlan(null)}>
+                Back
+              </button>
+            </section>
+          )}
+
+          {purgeReport && (
+            <section className="purge-panel">
+              <p className="summary-label">
+                Moved {formatBytes(purgeReport.trashed_bytes)} to the trash
+              </p>
+              <ul className="detail-folder-list">
+                {purgeReport.items.map((item) => (
+                  <li key={item.path} className="detail-folder-row">
+                    <span className="detail-folder-name">{item.path}</span>
+                    <span
+                      className={
+                        item.outcome === "trashed" ? "numeric" : "numeric error"
+                      }
+                    >
+                      {item.outcome === "trashed" ? "Trashed" : item.error}
+                    </span>
+                  </li>
+                ))}
+              </ul>
+              {purgeReport.trashed_bytes > 0 && (
+                <button className="cancel-btn" onClick={undoPurge}>
+                  Undo
+                </button>
+              )}
+            </section>
+          )}
+
+          {restoreResults.length > 0 && (
+            <section className="purge-panel">
+              <p className="summary-label">
+                Restored{" "}
+                {restoreResults.filter((item) => item.outcome === "restored").length}{" "}
+                of {restoreResults.length}
+              </p>
+              <ul className="detail-folder-list">
+                {restoreResults
+                  .filter((item) => item.outcome !== "restored")
+                  .map((item) => (
+                    <li key={item.id} className="detail-folder-row">
+                      <span className="detail-folder-name">{item.path ?? item.id}</span>
+                      <span className="numeric error">{item.error}</span>
+                    </li>
+                  ))}
+              </ul>
+            </section>
+          )}
+
           <button className="rescan-btn" onClick={startScan}>
             Rescan
           </button>